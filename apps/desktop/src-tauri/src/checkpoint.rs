@@ -0,0 +1,225 @@
+use git2::build::CheckoutBuilder;
+use git2::{DiffOptions, Repository};
+use serde::Serialize;
+
+use crate::git::{
+  add_all_to_index,
+  diff_to_files,
+  head_commit,
+  open_repo_at,
+  repo_workdir,
+  signature_or_default,
+  validate_git_ref,
+  GitDiffFile,
+  GitError,
+  GitErrorCode,
+};
+
+// Checkpoints live outside refs/heads and refs/tags so they never show up as
+// branches, but the refs keep the snapshot commits reachable.
+const CHECKPOINT_REF_PREFIX: &str = "refs/forks/checkpoints";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Checkpoint {
+  pub attempt: String,
+  pub turn: u32,
+  pub oid: String,
+  /// HEAD when the checkpoint was taken; `None` on an unborn branch.
+  pub head: Option<String>,
+  pub message: String,
+  /// Seconds since the Unix epoch.
+  pub created_at: i64,
+}
+
+fn validate_attempt(attempt: &str) -> Result<(), GitError> {
+  validate_git_ref(attempt)?;
+  if attempt.contains('/') {
+    return Err(GitError::new(
+      GitErrorCode::InvalidArgument,
+      "attempt id must be a single ref component",
+    ));
+  }
+  Ok(())
+}
+
+fn checkpoint_ref(attempt: &str, turn: u32) -> String {
+  format!("{}/{}/{}", CHECKPOINT_REF_PREFIX, attempt, turn)
+}
+
+/// Builds a tree of the worktree as `git add -A` would see it: tracked and
+/// untracked files, minus ignored ones. The index is only changed in memory
+/// and never written back, so the staged state is left alone.
+fn snapshot_tree(repo: &Repository) -> Result<git2::Tree<'_>, GitError> {
+  repo_workdir(repo)?;
+  let mut index = repo.index()?;
+  add_all_to_index(&mut index, &[])?;
+  let oid = index.write_tree_to(repo)?;
+  repo.find_tree(oid).map_err(GitError::from)
+}
+
+fn find_checkpoint<'repo>(
+  repo: &'repo Repository,
+  attempt: &str,
+  turn: u32,
+) -> Result<git2::Commit<'repo>, GitError> {
+  let reference = repo.find_reference(&checkpoint_ref(attempt, turn)).map_err(|err| {
+    if err.code() == git2::ErrorCode::NotFound {
+      GitError::new(
+        GitErrorCode::RefNotFound,
+        format!("checkpoint {}/{} not found", attempt, turn),
+      )
+    } else {
+      err.into()
+    }
+  })?;
+  reference.peel_to_commit().map_err(GitError::from)
+}
+
+fn list_checkpoints(repo: &Repository, attempt: &str) -> Result<Vec<Checkpoint>, GitError> {
+  let prefix = format!("{}/{}/", CHECKPOINT_REF_PREFIX, attempt);
+  let mut checkpoints = Vec::new();
+  for reference in repo.references_glob(&format!("{}*", prefix))? {
+    let reference = reference?;
+    let turn = match reference
+      .name()
+      .and_then(|name| name.strip_prefix(&prefix))
+      .and_then(|value| value.parse::<u32>().ok())
+    {
+      Some(value) => value,
+      None => continue,
+    };
+    let commit = reference.peel_to_commit()?;
+    checkpoints.push(Checkpoint {
+      attempt: attempt.to_string(),
+      turn,
+      oid: commit.id().to_string(),
+      head: commit.parent_ids().next().map(|oid| oid.to_string()),
+      message: commit.message().unwrap_or_default().trim_end().to_string(),
+      created_at: commit.time().seconds(),
+    });
+  }
+  checkpoints.sort_by_key(|checkpoint| checkpoint.turn);
+  Ok(checkpoints)
+}
+
+/// Snapshots the worktree as checkpoint `turn` of `attempt` (default: one past
+/// the latest). HEAD, the index and the worktree are not modified.
+#[tauri::command]
+pub fn git_checkpoint_create(
+  repo_path: String,
+  attempt: String,
+  turn: Option<u32>,
+  message: Option<String>,
+) -> Result<Checkpoint, GitError> {
+  validate_attempt(&attempt)?;
+  let repo = open_repo_at(&repo_path)?;
+  let turn = match turn {
+    Some(value) => value,
+    None => list_checkpoints(&repo, &attempt)?
+      .last()
+      .map(|checkpoint| checkpoint.turn + 1)
+      .unwrap_or(1),
+  };
+  let tree = snapshot_tree(&repo)?;
+  let head = head_commit(&repo)?;
+  let parents: Vec<&git2::Commit> = head.iter().collect();
+  let signature = signature_or_default(&repo)?;
+  let message = match message.filter(|value| !value.trim().is_empty()) {
+    Some(value) => value,
+    None => format!("checkpoint {}/{}", attempt, turn),
+  };
+  let oid = repo.commit(None, &signature, &signature, &message, &tree, &parents)?;
+  repo.reference(
+    &checkpoint_ref(&attempt, turn),
+    oid,
+    false,
+    &format!("checkpoint: {}", message),
+  )?;
+  Ok(Checkpoint {
+    attempt,
+    turn,
+    oid: oid.to_string(),
+    head: head.map(|commit| commit.id().to_string()),
+    message,
+    created_at: signature.when().seconds(),
+  })
+}
+
+#[tauri::command]
+pub fn git_checkpoint_list(
+  repo_path: String,
+  attempt: String,
+) -> Result<Vec<Checkpoint>, GitError> {
+  validate_attempt(&attempt)?;
+  let repo = open_repo_at(&repo_path)?;
+  list_checkpoints(&repo, &attempt)
+}
+
+/// Diffs checkpoint `from` against checkpoint `to`, or against the current
+/// worktree when `to` is omitted.
+#[tauri::command]
+pub fn git_checkpoint_diff(
+  repo_path: String,
+  attempt: String,
+  from: u32,
+  to: Option<u32>,
+  context_lines: Option<u32>,
+) -> Result<Vec<GitDiffFile>, GitError> {
+  validate_attempt(&attempt)?;
+  let repo = open_repo_at(&repo_path)?;
+  let old_tree = find_checkpoint(&repo, &attempt, from)?.tree()?;
+  let new_tree = match to {
+    Some(turn) => find_checkpoint(&repo, &attempt, turn)?.tree()?,
+    None => snapshot_tree(&repo)?,
+  };
+  let mut opts = DiffOptions::new();
+  opts.context_lines(context_lines.unwrap_or(3).min(200));
+  let mut diff = repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(&mut opts))?;
+  diff.find_similar(None)?;
+  diff_to_files(&diff)
+}
+
+/// Makes the worktree match checkpoint `turn`: changed files are rewritten and
+/// files created since are removed (ignored files are kept). HEAD and the index
+/// stay where they are, so the restored state shows up as unstaged changes.
+#[tauri::command]
+pub fn git_checkpoint_restore(
+  repo_path: String,
+  attempt: String,
+  turn: u32,
+) -> Result<(), GitError> {
+  validate_attempt(&attempt)?;
+  let repo = open_repo_at(&repo_path)?;
+  let tree = find_checkpoint(&repo, &attempt, turn)?.tree()?;
+  let mut checkout = CheckoutBuilder::new();
+  checkout
+    .force()
+    .remove_untracked(true)
+    .update_index(false);
+  repo
+    .checkout_tree(tree.as_object(), Some(&mut checkout))
+    .map_err(GitError::from)
+}
+
+/// Deletes checkpoint `turn`, or every checkpoint of `attempt` when omitted.
+#[tauri::command]
+pub fn git_checkpoint_delete(
+  repo_path: String,
+  attempt: String,
+  turn: Option<u32>,
+) -> Result<(), GitError> {
+  validate_attempt(&attempt)?;
+  let repo = open_repo_at(&repo_path)?;
+  let turns = match turn {
+    Some(value) => vec![value],
+    None => list_checkpoints(&repo, &attempt)?
+      .into_iter()
+      .map(|checkpoint| checkpoint.turn)
+      .collect(),
+  };
+  for turn in turns {
+    repo.find_reference(&checkpoint_ref(&attempt, turn))?.delete()?;
+  }
+  Ok(())
+}
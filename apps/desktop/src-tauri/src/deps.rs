@@ -0,0 +1,342 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::git::{open_repo_at, repo_relative_path, repo_workdir, GitError, GitErrorCode};
+
+pub const PROGRESS_EVENT: &str = "git/clone_deps_progress";
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GitCloneDepsOptions {
+  /// Hardlink instead of copying when reflinks aren't supported. Hardlinked
+  /// files share their contents, so an in-place edit shows up in both trees.
+  pub allow_hardlink: bool,
+}
+
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCloneDepsProgress {
+  pub target_path: String,
+  /// Entry of `paths` currently being cloned.
+  pub path: String,
+  pub files_done: u64,
+  pub files_total: u64,
+  pub bytes_done: u64,
+  pub bytes_total: u64,
+}
+
+#[derive(Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCloneDepsSummary {
+  pub cloned: Vec<String>,
+  /// Paths missing from the source or already present in the target.
+  pub skipped: Vec<String>,
+  pub files: u64,
+  pub bytes: u64,
+  pub reflinked: u64,
+  pub hardlinked: u64,
+  pub copied: u64,
+}
+
+#[cfg(target_os = "linux")]
+fn reflink(src: &Path, dst: &Path) -> io::Result<()> {
+  use std::os::raw::{c_int, c_ulong};
+  use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+  use std::os::unix::io::AsRawFd;
+
+  extern "C" {
+    fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+  }
+  // _IOW(0x94, 9, int)
+  const FICLONE: c_ulong = 0x4004_9409;
+
+  let source = fs::File::open(src)?;
+  let mode = source.metadata()?.permissions().mode();
+  let target = fs::OpenOptions::new()
+    .write(true)
+    .create_new(true)
+    .mode(mode)
+    .open(dst)?;
+  // SAFETY: both descriptors are open for the duration of the call.
+  if unsafe { ioctl(target.as_raw_fd(), FICLONE, source.as_raw_fd()) } == 0 {
+    return Ok(());
+  }
+  let err = io::Error::last_os_error();
+  drop(target);
+  let _ = fs::remove_file(dst);
+  Err(err)
+}
+
+#[cfg(target_os = "macos")]
+fn reflink(src: &Path, dst: &Path) -> io::Result<()> {
+  use std::ffi::CString;
+  use std::os::raw::{c_char, c_int};
+  use std::os::unix::ffi::OsStrExt;
+
+  extern "C" {
+    fn clonefile(src: *const c_char, dst: *const c_char, flags: u32) -> c_int;
+  }
+  const CLONE_NOFOLLOW: u32 = 0x0001;
+
+  let src = CString::new(src.as_os_str().as_bytes())?;
+  let dst = CString::new(dst.as_os_str().as_bytes())?;
+  // SAFETY: both arguments are valid NUL-terminated paths.
+  if unsafe { clonefile(src.as_ptr(), dst.as_ptr(), CLONE_NOFOLLOW) } == 0 {
+    Ok(())
+  } else {
+    Err(io::Error::last_os_error())
+  }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn reflink(_src: &Path, _dst: &Path) -> io::Result<()> {
+  Err(io::Error::new(io::ErrorKind::Unsupported, "reflinks are not supported"))
+}
+
+#[cfg(unix)]
+fn copy_symlink(target: &Path, dst: &Path) -> io::Result<()> {
+  std::os::unix::fs::symlink(target, dst)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(_target: &Path, _dst: &Path) -> io::Result<()> {
+  Err(io::Error::new(io::ErrorKind::Unsupported, "symlinks are not supported"))
+}
+
+/// Whether `err` means the filesystem can't reflink or hardlink at all, rather
+/// than that this one file couldn't be.
+fn is_unsupported(err: &io::Error) -> bool {
+  // EOPNOTSUPP/ENOTSUP and ENOTTY have no `ErrorKind` of their own.
+  #[cfg(target_os = "linux")]
+  const RAW: &[i32] = &[95, 25];
+  #[cfg(target_os = "macos")]
+  const RAW: &[i32] = &[45, 102, 25];
+  #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+  const RAW: &[i32] = &[];
+  matches!(
+    err.kind(),
+    io::ErrorKind::Unsupported | io::ErrorKind::CrossesDevices | io::ErrorKind::InvalidInput
+  ) || err.raw_os_error().is_some_and(|code| RAW.contains(&code))
+}
+
+/// Files and bytes under `path`, not following symlinks.
+fn measure(path: &Path) -> io::Result<(u64, u64)> {
+  let metadata = fs::symlink_metadata(path)?;
+  if metadata.is_dir() {
+    let mut total = (0, 0);
+    for entry in fs::read_dir(path)? {
+      let (files, bytes) = measure(&entry?.path())?;
+      total = (total.0 + files, total.1 + bytes);
+    }
+    Ok(total)
+  } else if metadata.is_file() {
+    Ok((1, metadata.len()))
+  } else {
+    Ok((0, 0))
+  }
+}
+
+struct Cloner<'a> {
+  source_root: PathBuf,
+  target_root: PathBuf,
+  // Cleared once the filesystem turns out not to support them, so that costs
+  // one failed syscall rather than one per file.
+  reflink: bool,
+  hardlink: bool,
+  progress: GitCloneDepsProgress,
+  last_report: Instant,
+  on_progress: &'a dyn Fn(&GitCloneDepsProgress),
+  summary: GitCloneDepsSummary,
+}
+
+impl Cloner<'_> {
+  fn report(&mut self, force: bool) {
+    if force || self.last_report.elapsed() >= PROGRESS_INTERVAL {
+      self.last_report = Instant::now();
+      (self.on_progress)(&self.progress);
+    }
+  }
+
+  fn clone_file(&mut self, src: &Path, dst: &Path) -> io::Result<()> {
+    if self.reflink {
+      match reflink(src, dst) {
+        Ok(()) => {
+          self.summary.reflinked += 1;
+          return Ok(());
+        }
+        Err(err) if is_unsupported(&err) => self.reflink = false,
+        // Anything else is specific to this file; try the next way for it.
+        Err(_) => {}
+      }
+    }
+    if self.hardlink {
+      match fs::hard_link(src, dst) {
+        Ok(()) => {
+          self.summary.hardlinked += 1;
+          return Ok(());
+        }
+        Err(err) if is_unsupported(&err) => self.hardlink = false,
+        Err(_) => {}
+      }
+    }
+    fs::copy(src, dst)?;
+    self.summary.copied += 1;
+    Ok(())
+  }
+
+  /// Absolute links into the source worktree are pointed at the target instead,
+  /// so the clone doesn't keep reading from the tree it was cloned from.
+  fn clone_symlink(&self, src: &Path, dst: &Path) -> io::Result<()> {
+    let target = fs::read_link(src)?;
+    let target = match target.strip_prefix(&self.source_root) {
+      Ok(rest) if target.is_absolute() => self.target_root.join(rest),
+      _ => target,
+    };
+    copy_symlink(&target, dst)
+  }
+
+  fn clone_entry(&mut self, src: &Path, dst: &Path) -> io::Result<()> {
+    let metadata = fs::symlink_metadata(src)?;
+    let file_type = metadata.file_type();
+    if file_type.is_symlink() {
+      self.clone_symlink(src, dst)?;
+    } else if file_type.is_dir() {
+      fs::create_dir(dst)?;
+      for entry in fs::read_dir(src)? {
+        let entry = entry?;
+        self.clone_entry(&entry.path(), &dst.join(entry.file_name()))?;
+      }
+      // Applied last so read-only directories can still be filled.
+      fs::set_permissions(dst, metadata.permissions())?;
+    } else if file_type.is_file() {
+      self.clone_file(src, dst)?;
+      self.summary.files += 1;
+      self.summary.bytes += metadata.len();
+      self.progress.files_done += 1;
+      self.progress.bytes_done += metadata.len();
+      self.report(false);
+    }
+    Ok(())
+  }
+}
+
+/// Clones ignored `paths` (e.g. `node_modules`, `target`) from the worktree at
+/// `source_path` into the one at `target_path`. Files are reflinked where the
+/// filesystem supports it and copied otherwise, or hardlinked first with
+/// `allow_hardlink`. Each path is cloned into a temporary sibling and renamed
+/// into place, so a failure never leaves a partial tree behind.
+pub fn clone_deps(
+  source_path: &str,
+  target_path: &str,
+  paths: &[String],
+  options: GitCloneDepsOptions,
+  on_progress: &dyn Fn(&GitCloneDepsProgress),
+) -> Result<GitCloneDepsSummary, GitError> {
+  if paths.is_empty() {
+    return Err(GitError::new(GitErrorCode::InvalidArgument, "no paths to clone"));
+  }
+  let source = open_repo_at(source_path)?;
+  let target = open_repo_at(target_path)?;
+  let source_root = fs::canonicalize(repo_workdir(&source)?)?;
+  let target_root = fs::canonicalize(repo_workdir(&target)?)?;
+  if source_root == target_root {
+    return Err(GitError::new(
+      GitErrorCode::InvalidArgument,
+      "source and target are the same worktree",
+    ));
+  }
+
+  let mut pending = Vec::new();
+  let mut skipped = Vec::new();
+  let (mut files_total, mut bytes_total) = (0, 0);
+  for path in paths {
+    let relative = repo_relative_path(path)?;
+    let src = source_root.join(relative);
+    let dst = target_root.join(relative);
+    // Tracked content belongs to checkout; only ignored paths are cloned.
+    if !source.is_path_ignored(relative)? {
+      return Err(GitError::new(
+        GitErrorCode::InvalidArgument,
+        format!("{} is not ignored", path),
+      ));
+    }
+    if fs::symlink_metadata(&src).is_err() || fs::symlink_metadata(&dst).is_ok() {
+      skipped.push(path.clone());
+      continue;
+    }
+    let (files, bytes) = measure(&src)?;
+    files_total += files;
+    bytes_total += bytes;
+    pending.push((path.clone(), src, dst));
+  }
+
+  let mut cloner = Cloner {
+    source_root: source_root.clone(),
+    target_root: target_root.clone(),
+    reflink: true,
+    hardlink: options.allow_hardlink,
+    progress: GitCloneDepsProgress {
+      target_path: target_path.to_string(),
+      path: String::new(),
+      files_done: 0,
+      files_total,
+      bytes_done: 0,
+      bytes_total,
+    },
+    last_report: Instant::now(),
+    on_progress,
+    summary: GitCloneDepsSummary {
+      skipped,
+      ..Default::default()
+    },
+  };
+  for (path, src, dst) in pending {
+    cloner.progress.path = path.clone();
+    cloner.report(true);
+    if let Some(parent) = dst.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    let name = dst.file_name().unwrap_or_default().to_string_lossy();
+    let staging = dst.with_file_name(format!(".{}.forks-clone", name));
+    if fs::symlink_metadata(&staging).is_ok() {
+      fs::remove_dir_all(&staging).or_else(|_| fs::remove_file(&staging))?;
+    }
+    let result = cloner
+      .clone_entry(&src, &staging)
+      .and_then(|_| fs::rename(&staging, &dst));
+    if let Err(err) = result {
+      let _ = fs::remove_dir_all(&staging).or_else(|_| fs::remove_file(&staging));
+      return Err(err.into());
+    }
+    cloner.summary.cloned.push(path);
+  }
+  cloner.report(true);
+  Ok(cloner.summary)
+}
+
+/// Clones ignored dependency directories into a new worktree; see `clone_deps`.
+/// Progress is emitted as `git/clone_deps_progress` events.
+// Runs off the main thread: cloning a large `node_modules` takes a while.
+#[tauri::command(async)]
+pub fn git_clone_deps(
+  app: AppHandle,
+  source_path: String,
+  target_path: String,
+  paths: Vec<String>,
+  options: Option<GitCloneDepsOptions>,
+) -> Result<GitCloneDepsSummary, GitError> {
+  clone_deps(
+    &source_path,
+    &target_path,
+    &paths,
+    options.unwrap_or_default(),
+    &|progress| {
+      let _ = app.emit(PROGRESS_EVENT, progress);
+    },
+  )
+}
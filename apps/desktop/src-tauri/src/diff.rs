@@ -1,11 +1,330 @@
-use similar::TextDiff;
+use serde::{Deserialize, Serialize};
+use similar::udiff::UnifiedDiffHunk;
+use similar::{Algorithm, Change, ChangeTag, DiffOp, DiffTag, TextDiff};
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
 
 // TODO: Use for review/PR diff rendering when the desktop UI lands.
 
-pub fn unified_diff(original: &str, modified: &str, context: usize) -> String {
-  TextDiff::from_lines(original, modified)
-    .unified_diff()
-    .context_radius(context)
-    .header("a", "b")
-    .to_string()
+// Deadline for a single diff; past it the algorithms fall back to a coarser
+// (still correct) result instead of blocking the caller.
+const DEFAULT_DIFF_TIMEOUT_MS: u64 = 5_000;
+const MAX_DIFF_TIMEOUT_MS: u64 = 60_000;
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffAlgorithm {
+  #[default]
+  Myers,
+  Patience,
+  Lcs,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiffOptions {
+  pub algorithm: DiffAlgorithm,
+  pub ignore_all_whitespace: bool,
+  pub ignore_trailing_whitespace: bool,
+  /// Drops hunks whose changes only add or remove blank lines.
+  pub ignore_blank_lines: bool,
+  pub timeout_ms: Option<u64>,
+}
+
+impl DiffOptions {
+  fn deadline(&self) -> Instant {
+    let timeout = self
+      .timeout_ms
+      .unwrap_or(DEFAULT_DIFF_TIMEOUT_MS)
+      .min(MAX_DIFF_TIMEOUT_MS);
+    Instant::now() + Duration::from_millis(timeout)
+  }
+
+  /// The value a line is compared by; rendering still uses the original text.
+  fn line_key<'a>(&self, line: &'a str) -> Cow<'a, str> {
+    if self.ignore_all_whitespace {
+      Cow::Owned(line.chars().filter(|ch| !ch.is_whitespace()).collect())
+    } else if self.ignore_trailing_whitespace {
+      Cow::Borrowed(line.trim_end())
+    } else {
+      Cow::Borrowed(line)
+    }
+  }
+}
+
+impl From<DiffAlgorithm> for Algorithm {
+  fn from(value: DiffAlgorithm) -> Self {
+    match value {
+      DiffAlgorithm::Myers => Algorithm::Myers,
+      DiffAlgorithm::Patience => Algorithm::Patience,
+      DiffAlgorithm::Lcs => Algorithm::Lcs,
+    }
+  }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffLineTag {
+  Equal,
+  Delete,
+  Insert,
+}
+
+/// Granularity of intra-line highlighting for changed lines.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum InlineGranularity {
+  Word,
+  Char,
+}
+
+/// A run of text within a changed line; `emphasized` runs are the parts that differ
+/// from the paired line on the other side.
+#[derive(Serialize)]
+pub struct DiffSpan {
+  pub value: String,
+  pub emphasized: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+  pub tag: DiffLineTag,
+  pub old_line: Option<u32>,
+  pub new_line: Option<u32>,
+  pub content: String,
+  pub spans: Option<Vec<DiffSpan>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+  pub header: String,
+  pub old_start: u32,
+  pub old_lines: u32,
+  pub new_start: u32,
+  pub new_lines: u32,
+  pub lines: Vec<DiffLine>,
+}
+
+/// Line diff of both inputs, returned as hunks of ops with `context` lines around each change.
+fn grouped_line_diff<'a>(
+  original: &'a str,
+  modified: &'a str,
+  context: usize,
+  options: &DiffOptions,
+  deadline: Instant,
+) -> (TextDiff<'a, 'a, 'a, str>, Vec<Vec<DiffOp>>) {
+  // The text diff only provides the line slices used for rendering. Its own ops are
+  // replaced below by a diff over the normalized keys, so an already-expired
+  // deadline keeps that first pass to a cheap prefix/suffix scan.
+  let text = TextDiff::configure()
+    .deadline(Instant::now())
+    .diff_lines(original, modified);
+  let old_keys: Vec<Cow<str>> = text
+    .old_slices()
+    .iter()
+    .map(|line| options.line_key(line))
+    .collect();
+  let new_keys: Vec<Cow<str>> = text
+    .new_slices()
+    .iter()
+    .map(|line| options.line_key(line))
+    .collect();
+  let ops = similar::capture_diff_deadline(
+    options.algorithm.into(),
+    &old_keys,
+    0..old_keys.len(),
+    &new_keys,
+    0..new_keys.len(),
+    Some(deadline),
+  );
+  let mut groups = similar::group_diff_ops(ops, context);
+  if options.ignore_blank_lines {
+    groups.retain(|ops| !only_blank_changes(ops, text.old_slices(), text.new_slices()));
+  }
+  (text, groups)
+}
+
+fn only_blank_changes(ops: &[DiffOp], old: &[&str], new: &[&str]) -> bool {
+  ops
+    .iter()
+    .filter(|op| op.tag() != DiffTag::Equal)
+    .all(|op| {
+      old[op.old_range()].iter().all(|line| line.trim().is_empty())
+        && new[op.new_range()].iter().all(|line| line.trim().is_empty())
+    })
+}
+
+pub fn unified_diff(
+  original: &str,
+  modified: &str,
+  context: usize,
+  options: &DiffOptions,
+) -> String {
+  let (diff, groups) =
+    grouped_line_diff(original, modified, context, options, options.deadline());
+  let mut output = String::new();
+  for (idx, ops) in groups.into_iter().enumerate() {
+    if idx == 0 {
+      output.push_str("--- a\n+++ b\n");
+    }
+    let _ = write!(output, "{}", UnifiedDiffHunk::new(ops, &diff, true));
+  }
+  output
+}
+
+/// Line-level diff grouped into hunks, mirroring the unified output without the text framing.
+/// With `inline` set, deleted/inserted lines also carry spans marking what changed within them.
+pub fn structured_diff(
+  original: &str,
+  modified: &str,
+  context: usize,
+  options: &DiffOptions,
+  inline: Option<InlineGranularity>,
+) -> Vec<DiffHunk> {
+  let deadline = options.deadline();
+  let (diff, groups) = grouped_line_diff(original, modified, context, options, deadline);
+  groups
+    .into_iter()
+    .map(|ops| {
+      let hunk = UnifiedDiffHunk::new(ops, &diff, true);
+      let (old_start, old_lines) = hunk_range(hunk.ops(), DiffOp::old_range);
+      let (new_start, new_lines) = hunk_range(hunk.ops(), DiffOp::new_range);
+      let mut lines = Vec::new();
+      for op in hunk.ops() {
+        match inline {
+          Some(InlineGranularity::Word) => {
+            for change in diff.iter_inline_changes_deadline(op, Some(deadline)) {
+              let mut spans = Vec::new();
+              for (emphasized, value) in change.iter_strings_lossy() {
+                push_span(&mut spans, &value, emphasized);
+              }
+              let content: String = spans.iter().map(|span| span.value.as_str()).collect();
+              let spans = (change.tag() != ChangeTag::Equal).then(|| trim_spans(spans));
+              lines.push(diff_line(
+                change.tag(),
+                change.old_index(),
+                change.new_index(),
+                &content,
+                spans,
+              ));
+            }
+          }
+          Some(InlineGranularity::Char) => {
+            let start = lines.len();
+            lines.extend(diff.iter_changes(op).map(line_from_change));
+            if op.tag() == DiffTag::Replace {
+              attach_char_spans(&mut lines[start..], deadline);
+            }
+          }
+          None => lines.extend(diff.iter_changes(op).map(line_from_change)),
+        }
+      }
+      DiffHunk {
+        header: hunk.header().to_string().trim_end().to_string(),
+        old_start,
+        old_lines,
+        new_start,
+        new_lines,
+        lines,
+      }
+    })
+    .collect()
+}
+
+fn line_from_change(change: Change<&str>) -> DiffLine {
+  diff_line(
+    change.tag(),
+    change.old_index(),
+    change.new_index(),
+    change.value(),
+    None,
+  )
+}
+
+fn diff_line(
+  tag: ChangeTag,
+  old_index: Option<usize>,
+  new_index: Option<usize>,
+  content: &str,
+  spans: Option<Vec<DiffSpan>>,
+) -> DiffLine {
+  DiffLine {
+    tag: match tag {
+      ChangeTag::Equal => DiffLineTag::Equal,
+      ChangeTag::Delete => DiffLineTag::Delete,
+      ChangeTag::Insert => DiffLineTag::Insert,
+    },
+    old_line: old_index.map(|index| index as u32 + 1),
+    new_line: new_index.map(|index| index as u32 + 1),
+    content: content.trim_end_matches(['\n', '\r']).to_string(),
+    spans,
+  }
+}
+
+/// Pairs the deleted and inserted lines of a replace op positionally and
+/// diffs each pair by characters. Unpaired lines are left without spans.
+fn attach_char_spans(lines: &mut [DiffLine], deadline: Instant) {
+  let split = lines
+    .iter()
+    .position(|line| line.tag == DiffLineTag::Insert)
+    .unwrap_or(lines.len());
+  let (deleted, inserted) = lines.split_at_mut(split);
+  for (old, new) in deleted.iter_mut().zip(inserted.iter_mut()) {
+    let diff = TextDiff::configure()
+      .deadline(deadline)
+      .diff_chars(old.content.as_str(), new.content.as_str());
+    let mut old_spans = Vec::new();
+    let mut new_spans = Vec::new();
+    for change in diff.iter_all_changes() {
+      match change.tag() {
+        ChangeTag::Equal => {
+          push_span(&mut old_spans, change.value(), false);
+          push_span(&mut new_spans, change.value(), false);
+        }
+        ChangeTag::Delete => push_span(&mut old_spans, change.value(), true),
+        ChangeTag::Insert => push_span(&mut new_spans, change.value(), true),
+      }
+    }
+    old.spans = Some(old_spans);
+    new.spans = Some(new_spans);
+  }
+}
+
+fn push_span(spans: &mut Vec<DiffSpan>, value: &str, emphasized: bool) {
+  match spans.last_mut() {
+    Some(last) if last.emphasized == emphasized => last.value.push_str(value),
+    _ => spans.push(DiffSpan {
+      value: value.to_string(),
+      emphasized,
+    }),
+  }
+}
+
+/// Drops the line terminator from the trailing spans so they line up with `DiffLine::content`.
+fn trim_spans(mut spans: Vec<DiffSpan>) -> Vec<DiffSpan> {
+  while let Some(last) = spans.last_mut() {
+    let trimmed_len = last.value.trim_end_matches(['\n', '\r']).len();
+    last.value.truncate(trimmed_len);
+    if !last.value.is_empty() {
+      break;
+    }
+    spans.pop();
+  }
+  spans
+}
+
+/// Returns the 1-based start and length of a hunk side. Like git, an empty
+/// side reports the line before the insertion point as its start.
+fn hunk_range(ops: &[DiffOp], range: fn(&DiffOp) -> std::ops::Range<usize>) -> (u32, u32) {
+  let start = ops.first().map(|op| range(op).start).unwrap_or(0);
+  let end = ops.last().map(|op| range(op).end).unwrap_or(0);
+  let len = end.saturating_sub(start) as u32;
+  if len == 0 {
+    (start as u32, 0)
+  } else {
+    (start as u32 + 1, len)
+  }
 }
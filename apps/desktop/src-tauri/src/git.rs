@@ -1,18 +1,122 @@
+use git2::build::CheckoutBuilder;
 use git2::{
+  BranchType,
+  Delta,
+  DiffOptions,
+  ErrorClass,
+  ErrorCode,
+  IndexAddOption,
+  IndexEntry,
+  IndexEntryExtendedFlag,
+  IndexTime,
+  MergeFileOptions,
   ObjectType,
+  Oid,
+  Pathspec,
+  PathspecFlags,
   Repository,
+  RebaseOptions,
   ResetType,
+  Signature,
+  Sort,
+  StashApplyOptions,
+  StashFlags,
+  StashSaveOptions,
   Worktree,
   WorktreeAddOptions,
   WorktreeLockStatus,
   WorktreePruneOptions,
 };
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::path::{Path, PathBuf};
 use std::sync::{Mutex, OnceLock};
 use std::time::{Duration, Instant};
 
+use crate::diff::{DiffHunk, DiffLine, DiffLineTag};
+
+/// Stable, machine-readable error codes so callers don't have to match on messages.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GitErrorCode {
+  NotARepo,
+  RefInvalid,
+  RefNotFound,
+  PathNotFound,
+  Exists,
+  BranchNotMerged,
+  BranchCheckedOut,
+  DirtyWorktree,
+  NothingToCommit,
+  Locked,
+  Conflict,
+  NotFastForward,
+  RefChanged,
+  InvalidArgument,
+  InvalidParams,
+  UnknownMethod,
+  Other,
+}
+
+/// Error returned by git commands, serialized as-is into Tauri command rejections
+/// and `RpcResponse.error`.
+#[derive(Serialize, Debug)]
+pub struct GitError {
+  pub code: GitErrorCode,
+  pub message: String,
+  pub details: Option<serde_json::Value>,
+}
+
+impl GitError {
+  pub fn new(code: GitErrorCode, message: impl Into<String>) -> Self {
+    Self {
+      code,
+      message: message.into(),
+      details: None,
+    }
+  }
+
+  pub fn with_details(mut self, details: serde_json::Value) -> Self {
+    self.details = Some(details);
+    self
+  }
+}
+
+impl fmt::Display for GitError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.message)
+  }
+}
+
+impl From<git2::Error> for GitError {
+  fn from(err: git2::Error) -> Self {
+    let code = match (err.code(), err.class()) {
+      (ErrorCode::NotFound, ErrorClass::Repository) => GitErrorCode::NotARepo,
+      (ErrorCode::NotFound | ErrorCode::UnbornBranch, _) => GitErrorCode::RefNotFound,
+      (ErrorCode::InvalidSpec | ErrorCode::Ambiguous, _) => GitErrorCode::RefInvalid,
+      (ErrorCode::Invalid, ErrorClass::Reference) => GitErrorCode::RefInvalid,
+      (ErrorCode::Exists, _) => GitErrorCode::Exists,
+      (ErrorCode::Locked, _) => GitErrorCode::Locked,
+      (ErrorCode::Modified, ErrorClass::Reference) => GitErrorCode::RefChanged,
+      (ErrorCode::Conflict | ErrorCode::MergeConflict | ErrorCode::Unmerged, _) => {
+        GitErrorCode::Conflict
+      }
+      (ErrorCode::Uncommitted | ErrorCode::IndexDirty, _) => GitErrorCode::DirtyWorktree,
+      _ => GitErrorCode::Other,
+    };
+    Self::new(code, err.message())
+  }
+}
+
+impl From<std::io::Error> for GitError {
+  fn from(err: std::io::Error) -> Self {
+    Self::new(GitErrorCode::Other, err.to_string())
+  }
+}
+
 // Repository cache: avoids reopening the same repo repeatedly
 const REPO_CACHE_TTL_SECS: u64 = 30;
 const REPO_CACHE_MAX_SIZE: usize = 16;
@@ -48,7 +152,7 @@ impl RepoCache {
     }
   }
 
-  fn get_or_open(&mut self, path: &Path) -> Result<&Repository, String> {
+  fn get_or_open(&mut self, path: &Path) -> Result<&Repository, GitError> {
     let now = Instant::now();
     let ttl = Duration::from_secs(REPO_CACHE_TTL_SECS);
 
@@ -58,12 +162,12 @@ impl RepoCache {
       self.evict_oldest();
     }
 
-    let canonical = std::fs::canonicalize(path).map_err(|err| err.to_string())?;
+    let canonical = std::fs::canonicalize(path)
+      .map_err(|err| GitError::new(GitErrorCode::NotARepo, err.to_string()))?;
 
     if !self.entries.contains_key(&canonical) {
       let repo = Repository::open(&canonical)
-        .or_else(|_| Repository::discover(&canonical))
-        .map_err(|err| err.to_string())?;
+        .or_else(|_| Repository::discover(&canonical))?;
       self.entries.insert(
         canonical.clone(),
         CachedRepo {
@@ -85,13 +189,13 @@ fn get_repo_cache() -> &'static Mutex<RepoCache> {
   REPO_CACHE.get_or_init(|| Mutex::new(RepoCache::new()))
 }
 
-fn with_cached_repo<F, T>(path: &str, f: F) -> Result<T, String>
+fn with_cached_repo<F, T>(path: &str, f: F) -> Result<T, GitError>
 where
-  F: FnOnce(&Repository) -> Result<T, String>,
+  F: FnOnce(&Repository) -> Result<T, GitError>,
 {
   let mut cache = get_repo_cache()
     .lock()
-    .map_err(|_| "repo cache lock poisoned".to_string())?;
+    .map_err(|_| GitError::new(GitErrorCode::Other, "repo cache lock poisoned"))?;
   let repo = cache.get_or_open(Path::new(path))?;
   f(repo)
 }
@@ -100,49 +204,112 @@ where
 /// Includes space, tilde, caret, colon, question mark, asterisk, brackets, backslash, at-sign, and braces.
 const GIT_REF_FORBIDDEN: &[char] = &[' ', '~', '^', ':', '?', '*', '[', ']', '\\', '@', '{'];
 
+fn invalid_ref(reason: &str) -> GitError {
+  GitError::new(GitErrorCode::RefInvalid, format!("invalid ref: {}", reason))
+}
+
+fn invalid_commit() -> GitError {
+  GitError::new(GitErrorCode::RefInvalid, "invalid commit")
+}
+
 /// Validates a git ref name according to git-check-ref-format rules.
 /// This provides defense-in-depth validation at the Rust boundary.
-fn validate_git_ref(name: &str) -> Result<(), String> {
+pub(crate) fn validate_git_ref(name: &str) -> Result<(), GitError> {
   if name.is_empty() || name.len() > 256 {
-    return Err("invalid ref: empty or too long".to_string());
+    return Err(invalid_ref("empty or too long"));
   }
   // Cannot start with dash (prevents option injection)
   if name.starts_with('-') {
-    return Err("invalid ref: starts with dash".to_string());
+    return Err(invalid_ref("starts with dash"));
   }
   // Cannot end with .lock
   if name.ends_with(".lock") {
-    return Err("invalid ref: ends with .lock".to_string());
+    return Err(invalid_ref("ends with .lock"));
   }
   // Cannot contain consecutive slashes
   if name.contains("//") {
-    return Err("invalid ref: contains consecutive slashes".to_string());
+    return Err(invalid_ref("contains consecutive slashes"));
   }
   // Cannot contain @{
   if name.contains("@{") {
-    return Err("invalid ref: contains @{".to_string());
+    return Err(invalid_ref("contains @{"));
   }
   // Cannot contain control characters (0x00-0x1F and 0x7F)
   for ch in name.chars() {
     let code = ch as u32;
     if code <= 0x1f || code == 0x7f {
-      return Err("invalid ref: contains control character".to_string());
+      return Err(invalid_ref("contains control character"));
     }
   }
   // Cannot contain forbidden git characters
   if name.chars().any(|ch| GIT_REF_FORBIDDEN.contains(&ch)) {
-    return Err("invalid ref: contains forbidden character".to_string());
+    return Err(invalid_ref("contains forbidden character"));
   }
   // Check each component between slashes
   for component in name.split('/') {
     if component.is_empty() || component.starts_with('.') || component.ends_with('.') {
-      return Err("invalid ref: invalid path component".to_string());
+      return Err(invalid_ref("invalid path component"));
     }
   }
   Ok(())
 }
 
+/// Validates a revision expression such as `HEAD~1`, `main^2`, `HEAD@{1}` or
+/// `main:src`. Only what could be mistaken for an option or is never valid is
+/// rejected here; `revparse_single` reports anything else.
+fn validate_revspec(spec: &str) -> Result<(), GitError> {
+  if spec.is_empty() || spec.len() > 4096 {
+    return Err(invalid_ref("empty or too long"));
+  }
+  if spec.starts_with('-') {
+    return Err(invalid_ref("starts with dash"));
+  }
+  if spec.chars().any(|ch| ch <= '\x1f' || ch == '\x7f') {
+    return Err(invalid_ref("contains control character"));
+  }
+  Ok(())
+}
+
+/// Options for `git_create_worktree`.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GitWorktreeOptions {
+  /// Commit a new branch or detached HEAD starts at; any rev, e.g. a checkpoint ref.
+  pub start_point: Option<String>,
+  /// Detach HEAD at the start point, falling back to `branch` and then HEAD.
+  pub detached: bool,
+  /// Register the worktree without writing an index or any files, e.g. to set up
+  /// a sparse checkout first.
+  pub no_checkout: bool,
+  /// Lock the worktree so it isn't pruned; implied by `lock_reason`.
+  pub lock: bool,
+  pub lock_reason: Option<String>,
+  /// Start in a cone-mode sparse checkout of these directories. With
+  /// `no_checkout` the patterns are written but nothing is checked out.
+  pub sparse_paths: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitPrunedWorktree {
+  pub name: String,
+  /// Where the worktree used to be checked out.
+  pub path: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitSparseCheckout {
+  pub enabled: bool,
+  pub cone: bool,
+  /// Directories checked out in full; only known in cone mode.
+  pub paths: Vec<String>,
+  /// Raw lines of `info/sparse-checkout`.
+  pub patterns: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct WorktreeInfo {
   pub path: String,
   pub head: String,
@@ -150,29 +317,335 @@ pub struct WorktreeInfo {
   pub bare: bool,
   pub detached: bool,
   pub locked: bool,
+  pub lock_reason: Option<String>,
   pub prunable: bool,
+  /// Admin entry name under `.git/worktrees`; `None` for the main worktree.
+  pub name: Option<String>,
+  /// The worktree's git dir: its admin dir, or `.git` for the main worktree.
+  pub gitdir: String,
+  pub path_exists: bool,
+  /// Only computed when status is requested and the path exists.
+  pub dirty: Option<bool>,
+  pub changed_files: Option<usize>,
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct GitStatusEntry {
   pub path: String,
+  /// Combined state, preferring the more significant of the index and worktree.
+  pub status: String,
+  /// State of HEAD vs the index; `None` when nothing is staged for the path.
+  pub index_status: Option<String>,
+  /// State of the index vs the worktree; `None` when the worktree matches the index.
+  pub worktree_status: Option<String>,
+  /// Previous path when the entry is a detected rename.
+  pub orig_path: Option<String>,
+}
+
+/// Branch header of `git status`: what HEAD points at and how it tracks upstream.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBranchHeader {
+  /// `None` when HEAD is detached.
+  pub branch: Option<String>,
+  /// `None` on an unborn branch.
+  pub head: Option<String>,
+  pub detached: bool,
+  pub upstream: Option<String>,
+  pub ahead: usize,
+  pub behind: usize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStatusSummary {
+  pub branch: GitBranchHeader,
+  pub entries: Vec<GitStatusEntry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitDiffFile {
   pub status: String,
+  pub old_path: Option<String>,
+  pub new_path: Option<String>,
+  pub binary: bool,
+  pub hunks: Vec<DiffHunk>,
+}
+
+/// Which two sides `git_diff` compares.
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum GitDiffMode {
+  /// `from` tree against `to` tree.
+  TreeToTree,
+  /// `from` tree (default HEAD) against the index, like `git diff --cached`.
+  TreeToIndex,
+  /// Index against the working directory, like `git diff`.
+  IndexToWorkdir,
+  /// `from` tree (default HEAD) against the working directory, like `git diff HEAD`.
+  HeadToWorkdir,
+  /// Merge base of `from` and `to` against `to`, like `git diff from...to`.
+  BranchToBranch,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GitSignature {
+  pub name: String,
+  pub email: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCommitInfo {
+  pub oid: String,
+  pub parents: Vec<String>,
+  pub author: GitSignature,
+  pub committer: GitSignature,
+  /// Seconds since the Unix epoch.
+  pub author_time: i64,
+  pub commit_time: i64,
+  pub summary: String,
+  pub body: Option<String>,
+}
+
+/// How `git_merge` combines the other branch into HEAD.
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GitMergeMode {
+  /// Only move HEAD forward; fails if the histories have diverged.
+  FastForwardOnly,
+  /// Fast-forward when possible, otherwise create a merge commit.
+  Merge,
+  /// Commit the combined changes on HEAD with a single parent.
+  Squash,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GitMergeStatus {
+  UpToDate,
+  FastForward,
+  Merged,
+  Squashed,
+  Conflicts,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitMergeResult {
+  pub status: GitMergeStatus,
+  /// New HEAD commit; `None` for dry runs and when nothing was changed.
+  pub oid: Option<String>,
+  /// Paths that conflict between the branches or with uncommitted edits.
+  pub conflicts: Vec<String>,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GitCherryPickStatus {
+  Picked,
+  Conflicts,
+  Aborted,
 }
 
-fn open_repo(path: &str) -> Result<Repository, String> {
-  Repository::discover(path).map_err(|err| err.to_string())
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitCherryPickResult {
+  pub status: GitCherryPickStatus,
+  /// Oids of the new commits, in order.
+  pub picked: Vec<String>,
+  /// Source commits whose changes were already on HEAD.
+  pub skipped: Vec<String>,
+  /// Source commit that could not be applied.
+  pub failed: Option<String>,
+  pub conflicts: Vec<String>,
+  /// Commits after `failed` that were not attempted.
+  pub remaining: Vec<String>,
 }
 
-fn open_repo_at(path: &str) -> Result<Repository, String> {
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum GitCherryPickAction {
+  #[default]
+  Start,
+  Continue,
+  Skip,
+  Abort,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum GitRebaseAction {
+  Start,
+  Continue,
+  Skip,
+  Abort,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GitRebaseStatus {
+  Completed,
+  Conflicts,
+  Aborted,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRebaseResult {
+  pub status: GitRebaseStatus,
+  /// HEAD once the rebase has completed or been aborted.
+  pub head: Option<String>,
+  /// Original commit being applied when the rebase stopped.
+  pub current: Option<String>,
+  /// 1-based position of `current` among `total` commits.
+  pub step: usize,
+  pub total: usize,
+  pub conflicts: Vec<String>,
+}
+
+/// One stage of a conflicted path. `content` is omitted for binary blobs.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictSide {
+  pub oid: String,
+  pub mode: u32,
+  pub binary: bool,
+  pub content: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitConflictEntry {
+  pub path: String,
+  pub ancestor: Option<GitConflictSide>,
+  pub ours: Option<GitConflictSide>,
+  pub theirs: Option<GitConflictSide>,
+  /// Both sides merged with conflict markers; only set when requested and both
+  /// sides are text files.
+  pub merged: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum GitConflictResolution {
+  Ours,
+  Theirs,
+  Custom,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitStashEntry {
+  /// Position in the stash list; 0 is the most recent (`stash@{0}`).
+  pub index: usize,
+  pub message: String,
+  pub oid: String,
+}
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum GitContentEncoding {
+  Utf8,
+  Base64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitFileContent {
+  pub path: String,
+  pub oid: String,
+  pub mode: i32,
+  pub size: usize,
+  pub binary: bool,
+  /// Text as-is for UTF-8 files, base64 otherwise; see `encoding`.
+  pub content: String,
+  pub encoding: GitContentEncoding,
+}
+
+#[derive(Serialize, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum GitTreeEntryKind {
+  Blob,
+  Tree,
+  Submodule,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitTreeEntry {
+  pub name: String,
+  pub path: String,
+  pub kind: GitTreeEntryKind,
+  pub mode: i32,
+  pub oid: String,
+  /// Blob size in bytes; `None` for trees and submodules.
+  pub size: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum GitBranchKind {
+  Local,
+  Remote,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitBranchInfo {
+  /// Short name, e.g. `main` or `origin/main`.
+  pub name: String,
+  pub kind: GitBranchKind,
+  pub oid: String,
+  pub upstream: Option<String>,
+  /// Commits on the branch but not its upstream; 0 without an upstream.
+  pub ahead: usize,
+  pub behind: usize,
+  /// Seconds since the Unix epoch.
+  pub commit_time: i64,
+  pub summary: String,
+  /// Worktree that has the branch checked out, if any.
+  pub worktree_path: Option<String>,
+}
+
+/// How `head` relates to `base`: commits only on `head` (ahead), commits only on
+/// `base` (behind) and where they forked.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitRefComparison {
+  pub base: String,
+  pub head: String,
+  pub base_oid: String,
+  /// `None` only when `error` is set.
+  pub head_oid: Option<String>,
+  pub ahead: usize,
+  pub behind: usize,
+  /// `None` when the two histories are unrelated.
+  pub merge_base: Option<String>,
+  /// Whether `base` can be fast-forwarded to `head`, i.e. nothing is behind.
+  pub can_fast_forward: bool,
+  /// Why `head` couldn't be compared, e.g. a deleted branch in a batch.
+  pub error: Option<GitError>,
+}
+
+fn open_repo(path: &str) -> Result<Repository, GitError> {
+  Repository::discover(path).map_err(GitError::from)
+}
+
+pub(crate) fn open_repo_at(path: &str) -> Result<Repository, GitError> {
   Repository::open(path)
     .or_else(|_| Repository::discover(path))
-    .map_err(|err| err.to_string())
+    .map_err(GitError::from)
 }
 
-fn repo_workdir(repo: &Repository) -> Result<&Path, String> {
+pub(crate) fn repo_workdir(repo: &Repository) -> Result<&Path, GitError> {
   repo
     .workdir()
-    .ok_or_else(|| "repository has no working directory".to_string())
+    .ok_or_else(|| {
+      GitError::new(GitErrorCode::InvalidArgument, "repository has no working directory")
+    })
 }
 
 fn branch_from_head(repo: &Repository) -> Option<String> {
@@ -200,20 +673,34 @@ fn is_detached(repo: &Repository) -> bool {
   repo.head_detached().unwrap_or(false)
 }
 
-fn worktree_info_for_path(
+/// Describes the worktree `repo` was opened for. Lock and prune state are left
+/// for the caller, which knows whether it is a linked worktree.
+fn worktree_info(
+  repo: &Repository,
   path: &Path,
-  locked: bool,
-  prunable: bool,
-) -> Result<WorktreeInfo, String> {
-  let repo = Repository::open(path).map_err(|err| err.to_string())?;
+  include_status: bool,
+) -> Result<WorktreeInfo, GitError> {
+  let path_exists = path.is_dir();
+  let (dirty, changed_files) = if include_status && path_exists {
+    let count = collect_status(repo, false)?.len();
+    (Some(count > 0), Some(count))
+  } else {
+    (None, None)
+  };
   Ok(WorktreeInfo {
     path: path.to_string_lossy().to_string(),
-    head: head_oid_string(&repo),
-    branch: branch_from_head(&repo),
+    head: head_oid_string(repo),
+    branch: branch_from_head(repo),
     bare: repo.is_bare(),
-    detached: is_detached(&repo),
-    locked,
-    prunable,
+    detached: is_detached(repo),
+    locked: false,
+    lock_reason: None,
+    prunable: false,
+    name: None,
+    gitdir: repo.path().to_string_lossy().trim_end_matches('/').to_string(),
+    path_exists,
+    dirty,
+    changed_files,
   })
 }
 
@@ -224,320 +711,3348 @@ fn worktree_name_from_path(path: &Path, fallback: &str) -> String {
   fallback.replace("/", "-")
 }
 
-fn create_branch_at_head(
+/// Creates `branch` at `start_point`, or at HEAD when omitted.
+fn create_branch_at(
   repo: &Repository,
   branch: &str,
-) -> Result<(), String> {
-  let head = repo.head().map_err(|err| err.to_string())?;
-  let commit = head
-    .peel(ObjectType::Commit)
-    .map_err(|err| err.to_string())?
-    .into_commit()
-    .map_err(|_| "invalid commit".to_string())?;
+  start_point: Option<&str>,
+) -> Result<(), GitError> {
+  let commit = match start_point {
+    Some(spec) => resolve_commit(repo, spec)?,
+    None => {
+      let head = repo.head()?;
+      head
+        .peel(ObjectType::Commit)?
+        .into_commit()
+        .map_err(|_| invalid_commit())?
+    }
+  };
   repo
     .branch(branch, &commit, false)
     .map(|_| ())
-    .map_err(|err| err.to_string())
+    .map_err(GitError::from)
+}
+
+/// Registers a worktree like `git worktree add --no-checkout`: an admin dir
+/// under `<commondir>/worktrees/<name>` with HEAD set to `head`, and a `.git`
+/// file in `path` pointing back at it. No index is written and no files are
+/// checked out; that is left to `populate`. libgit2 always checks out, so this
+/// is used when that's unwanted or HEAD should be detached. If any step fails,
+/// the admin dir and whatever was created in `path` are removed again.
+fn add_worktree_unpopulated(
+  repo: &Repository,
+  name: &str,
+  path: &Path,
+  head: &str,
+  populate: impl FnOnce(&Repository) -> Result<(), GitError>,
+) -> Result<(), GitError> {
+  let admin = repo.commondir().join("worktrees").join(name);
+  if admin.exists() {
+    return Err(GitError::new(
+      GitErrorCode::Exists,
+      format!("worktree {} already exists", name),
+    ));
+  }
+  if path.exists() && std::fs::read_dir(path)?.next().is_some() {
+    return Err(GitError::new(GitErrorCode::Exists, "worktree path is not empty"));
+  }
+  let created = !path.exists();
+  let result = (|| {
+    std::fs::create_dir_all(path)?;
+    std::fs::create_dir_all(&admin)?;
+    let path = std::fs::canonicalize(path)?;
+    let admin = std::fs::canonicalize(&admin)?;
+    std::fs::write(admin.join("HEAD"), format!("{}\n", head))?;
+    std::fs::write(admin.join("commondir"), "../..\n")?;
+    std::fs::write(admin.join("gitdir"), format!("{}\n", path.join(".git").display()))?;
+    std::fs::write(path.join(".git"), format!("gitdir: {}\n", admin.display()))?;
+    populate(&Repository::open(&path)?)
+  })();
+  if result.is_err() {
+    let _ = std::fs::remove_dir_all(&admin);
+    if created {
+      let _ = std::fs::remove_dir_all(path);
+    } else if let Ok(entries) = std::fs::read_dir(path) {
+      // The directory was empty, so everything in it came from this attempt.
+      for entry in entries.flatten() {
+        let entry = entry.path();
+        let _ = std::fs::remove_dir_all(&entry).or_else(|_| std::fs::remove_file(&entry));
+      }
+    }
+  }
+  result
 }
 
 fn resolve_commit<'repo>(
   repo: &'repo Repository,
   spec: &str,
-) -> Result<git2::Commit<'repo>, String> {
-  let object = repo
-    .revparse_single(spec)
-    .map_err(|err| err.to_string())?;
+) -> Result<git2::Commit<'repo>, GitError> {
+  let object = repo.revparse_single(spec)?;
   object
-    .peel(ObjectType::Commit)
-    .map_err(|err| err.to_string())?
+    .peel(ObjectType::Commit)?
     .into_commit()
-    .map_err(|_| "invalid commit".to_string())
+    .map_err(|_| invalid_commit())
 }
 
-fn ensure_clean_worktree(path: &Path) -> Result<(), String> {
-  let repo = Repository::open(path).map_err(|err| err.to_string())?;
-  let statuses = repo
-    .statuses(None)
-    .map_err(|err| err.to_string())?;
-  if statuses.is_empty() {
-    Ok(())
-  } else {
-    Err("worktree has uncommitted changes".to_string())
+pub(crate) fn head_commit(repo: &Repository) -> Result<Option<git2::Commit<'_>>, GitError> {
+  match repo.head() {
+    Ok(head) => head
+      .peel(ObjectType::Commit)?
+      .into_commit()
+      .map(Some)
+      .map_err(|_| invalid_commit()),
+    Err(err) if err.code() == git2::ErrorCode::UnbornBranch => Ok(None),
+    Err(err) => Err(err.into()),
   }
 }
 
-fn status_to_kind(status: git2::Status) -> Option<String> {
-  if status.is_conflicted() {
-    return Some("conflicted".to_string());
+fn resolve_tree<'repo>(
+  repo: &'repo Repository,
+  spec: &str,
+) -> Result<git2::Tree<'repo>, GitError> {
+  repo
+    .revparse_single(spec)?
+    .peel_to_tree()
+    .map_err(GitError::from)
+}
+
+/// Resolves `spec` to a tree, treating HEAD on an unborn branch as the empty tree.
+fn resolve_tree_or_unborn<'repo>(
+  repo: &'repo Repository,
+  spec: Option<&str>,
+) -> Result<Option<git2::Tree<'repo>>, GitError> {
+  match spec {
+    Some(spec) => resolve_tree(repo, spec).map(Some),
+    None => match head_commit(repo)? {
+      Some(commit) => commit.tree().map(Some).map_err(GitError::from),
+      None => Ok(None),
+    },
   }
-  if status.is_index_deleted() || status.is_wt_deleted() {
-    return Some("deleted".to_string());
+}
+
+fn required_rev<'a>(spec: Option<&'a str>, side: &str) -> Result<&'a str, GitError> {
+  spec.ok_or_else(|| {
+    GitError::new(
+      GitErrorCode::InvalidArgument,
+      format!("diff mode requires `{}`", side),
+    )
+  })
+}
+
+fn non_empty(value: &str) -> Option<&str> {
+  (!value.is_empty()).then_some(value)
+}
+
+fn delta_to_kind(delta: Delta) -> &'static str {
+  match delta {
+    Delta::Added => "added",
+    Delta::Deleted => "deleted",
+    Delta::Modified => "modified",
+    Delta::Renamed => "renamed",
+    Delta::Copied => "copied",
+    Delta::Ignored => "ignored",
+    Delta::Untracked => "untracked",
+    Delta::Typechange => "typechange",
+    Delta::Conflicted => "conflicted",
+    Delta::Unreadable => "unreadable",
+    Delta::Unmodified => "unmodified",
   }
-  if status.is_index_new() {
-    return Some("added".to_string());
+}
+
+pub(crate) fn diff_to_files(diff: &git2::Diff) -> Result<Vec<GitDiffFile>, GitError> {
+  let mut files = Vec::with_capacity(diff.deltas().len());
+  for idx in 0..diff.deltas().len() {
+    let patch = git2::Patch::from_diff(diff, idx)?;
+    let delta = match &patch {
+      Some(patch) => patch.delta(),
+      None => diff
+        .get_delta(idx)
+        .ok_or_else(|| GitError::new(GitErrorCode::Other, "diff delta out of range"))?,
+    };
+    let path_of = |file: git2::DiffFile| {
+      file
+        .exists()
+        .then(|| file.path().map(|value| value.to_string_lossy().to_string()))
+        .flatten()
+    };
+    let status = delta.status();
+    let old_path = path_of(delta.old_file());
+    let new_path = path_of(delta.new_file());
+    let binary = delta.flags().is_binary();
+
+    let mut hunks = Vec::new();
+    if let Some(patch) = patch.as_ref().filter(|_| !binary) {
+      for hunk_idx in 0..patch.num_hunks() {
+        let (hunk, line_count) = patch.hunk(hunk_idx)?;
+        let mut lines = Vec::with_capacity(line_count);
+        for line_idx in 0..line_count {
+          let line = patch.line_in_hunk(hunk_idx, line_idx)?;
+          let tag = match line.origin() {
+            ' ' => DiffLineTag::Equal,
+            '-' => DiffLineTag::Delete,
+            '+' => DiffLineTag::Insert,
+            // End-of-file newline markers carry no line content of their own.
+            _ => continue,
+          };
+          let content = String::from_utf8_lossy(line.content());
+          lines.push(DiffLine {
+            tag,
+            old_line: line.old_lineno(),
+            new_line: line.new_lineno(),
+            content: content.trim_end_matches(['\n', '\r']).to_string(),
+            spans: None,
+          });
+        }
+        hunks.push(DiffHunk {
+          header: String::from_utf8_lossy(hunk.header()).trim_end().to_string(),
+          old_start: hunk.old_start(),
+          old_lines: hunk.old_lines(),
+          new_start: hunk.new_start(),
+          new_lines: hunk.new_lines(),
+          lines,
+        });
+      }
+    }
+
+    files.push(GitDiffFile {
+      status: delta_to_kind(status).to_string(),
+      old_path,
+      new_path,
+      binary,
+      hunks,
+    });
   }
-  if status.is_wt_new() {
-    return Some("untracked".to_string());
+  Ok(files)
+}
+
+fn from_signature(value: &Signature) -> GitSignature {
+  GitSignature {
+    name: String::from_utf8_lossy(value.name_bytes()).to_string(),
+    email: String::from_utf8_lossy(value.email_bytes()).to_string(),
   }
-  if status.is_index_renamed() || status.is_wt_renamed() {
-    return Some("renamed".to_string());
+}
+
+fn commit_info(commit: &git2::Commit) -> GitCommitInfo {
+  let author = commit.author();
+  let committer = commit.committer();
+  GitCommitInfo {
+    oid: commit.id().to_string(),
+    parents: commit.parent_ids().map(|oid| oid.to_string()).collect(),
+    author: from_signature(&author),
+    committer: from_signature(&committer),
+    author_time: author.when().seconds(),
+    commit_time: committer.when().seconds(),
+    summary: commit.summary().unwrap_or_default().to_string(),
+    body: commit.body().map(|value| value.to_string()),
   }
-  if status.is_index_typechange() || status.is_wt_typechange() {
-    return Some("typechange".to_string());
+}
+
+/// Whether `commit` changes anything under the diff pathspec. Like git's default
+/// history simplification, a merge only counts if it differs from every parent.
+fn commit_touches_paths(
+  repo: &Repository,
+  commit: &git2::Commit,
+  opts: &mut DiffOptions,
+  first_parent: bool,
+) -> Result<bool, GitError> {
+  let tree = commit.tree()?;
+  let parent_count = if first_parent {
+    commit.parent_count().min(1)
+  } else {
+    commit.parent_count()
+  };
+  if parent_count == 0 {
+    let diff = repo.diff_tree_to_tree(None, Some(&tree), Some(opts))?;
+    return Ok(diff.deltas().len() > 0);
   }
-  if status.is_index_modified() || status.is_wt_modified() {
-    return Some("modified".to_string());
+  for idx in 0..parent_count {
+    let parent_tree = commit.parent(idx)?.tree()?;
+    let diff = repo.diff_tree_to_tree(Some(&parent_tree), Some(&tree), Some(opts))?;
+    if diff.deltas().len() == 0 {
+      return Ok(false);
+    }
   }
-  None
+  Ok(true)
 }
 
-#[tauri::command]
-pub fn git_is_repo(path: String) -> Result<bool, String> {
-  Ok(open_repo(&path).is_ok())
+fn conflict_paths(index: &git2::Index) -> Result<Vec<String>, GitError> {
+  let mut paths = Vec::new();
+  for conflict in index.conflicts()? {
+    let conflict = conflict?;
+    let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+    if let Some(entry) = entry {
+      let path = String::from_utf8_lossy(&entry.path).to_string();
+      if !paths.contains(&path) {
+        paths.push(path);
+      }
+    }
+  }
+  Ok(paths)
 }
 
-#[tauri::command]
-pub fn git_repo_root(path: String) -> Result<String, String> {
-  let repo = open_repo(&path)?;
-  let workdir = repo_workdir(&repo)?;
-  Ok(workdir.to_string_lossy().to_string())
+/// Paths with uncommitted edits (including untracked files) that moving the
+/// worktree from HEAD to `tree` would overwrite.
+fn checkout_conflicts(repo: &Repository, tree: &git2::Tree) -> Result<Vec<String>, GitError> {
+  let head_tree = head_commit(repo)?.map(|commit| commit.tree()).transpose()?;
+  let diff = repo.diff_tree_to_tree(head_tree.as_ref(), Some(tree), None)?;
+  dirty_paths_in_diff(repo, &diff)
 }
 
-#[tauri::command]
-pub fn git_default_branch(repo_path: String) -> Result<String, String> {
-  with_cached_repo(&repo_path, |repo| {
-    if let Ok(reference) = repo.find_reference("refs/remotes/origin/HEAD") {
-      if let Some(target) = reference.symbolic_target() {
-        if let Some(stripped) = target.strip_prefix("refs/remotes/origin/") {
-          return Ok(stripped.to_string());
-        }
-        return Ok(target.to_string());
+/// Paths touched by `diff` that have uncommitted edits or are untracked.
+fn dirty_paths_in_diff(repo: &Repository, diff: &git2::Diff) -> Result<Vec<String>, GitError> {
+  let mut changed = HashSet::new();
+  for delta in diff.deltas() {
+    for file in [delta.old_file(), delta.new_file()] {
+      if let Some(path) = file.path() {
+        changed.insert(path.to_string_lossy().to_string());
       }
     }
-    if let Some(branch) = branch_from_head(repo) {
-      return Ok(branch);
+  }
+  if changed.is_empty() {
+    return Ok(Vec::new());
+  }
+  let mut options = git2::StatusOptions::new();
+  options
+    .include_untracked(true)
+    .recurse_untracked_dirs(true)
+    .include_ignored(false);
+  let statuses = repo.statuses(Some(&mut options))?;
+  let skipped = skip_worktree_paths(&repo.index()?);
+  let mut paths = Vec::new();
+  for entry in statuses.iter() {
+    if is_sparse_deletion(entry.status(), entry.path(), &skipped) {
+      continue;
     }
-    Ok("main".to_string())
-  })
+    let path = match entry.path() {
+      Some(value) => value.to_string(),
+      None => continue,
+    };
+    if entry.status() != git2::Status::CURRENT && changed.contains(&path) {
+      paths.push(path);
+    }
+  }
+  Ok(paths)
 }
 
-#[tauri::command]
-pub fn git_current_branch(path: String) -> Result<String, String> {
-  with_cached_repo(&path, |repo| {
-    Ok(branch_from_head(repo).unwrap_or_default())
-  })
+/// Safely moves the index and worktree from HEAD to `tree`. In a cone-mode
+/// sparse checkout, paths outside the cone only have their index entries
+/// updated (still flagged skip-worktree): libgit2 ignores the sparse settings
+/// and would refuse to update their missing files.
+fn checkout_tree_safe(repo: &Repository, tree: &git2::Tree) -> Result<(), GitError> {
+  let sparse = read_sparse_checkout(repo)?;
+  if !sparse.cone {
+    repo.checkout_tree(tree.as_object(), Some(CheckoutBuilder::new().safe()))?;
+    return Ok(());
+  }
+  let workdir = repo_workdir(repo)?.to_path_buf();
+  let head_tree = head_commit(repo)?.map(|commit| commit.tree()).transpose()?;
+  let diff = repo.diff_tree_to_tree(head_tree.as_ref(), Some(tree), None)?;
+  let mut inside = HashSet::new();
+  let mut outside = HashSet::new();
+  for delta in diff.deltas() {
+    for file in [delta.old_file(), delta.new_file()] {
+      if let Some(path) = file.path().and_then(Path::to_str) {
+        let missing = std::fs::symlink_metadata(workdir.join(path)).is_err();
+        if missing && !in_sparse_cone(path, &sparse.paths) {
+          outside.insert(path.to_string());
+        } else {
+          inside.insert(path.to_string());
+        }
+      }
+    }
+  }
+  if !inside.is_empty() {
+    let mut checkout = CheckoutBuilder::new();
+    checkout.safe().disable_pathspec_match(true);
+    for path in &inside {
+      checkout.path(path);
+    }
+    repo.checkout_tree(tree.as_object(), Some(&mut checkout))?;
+  }
+  if outside.is_empty() {
+    return Ok(());
+  }
+  let mut index = repo.index()?;
+  for path in &outside {
+    match tree.get_path(Path::new(path)) {
+      Ok(entry) => index.add(&IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode: entry.filemode() as u32,
+        uid: 0,
+        gid: 0,
+        file_size: 0,
+        id: entry.id(),
+        flags: path.len().min(0xfff) as u16,
+        flags_extended: IndexEntryExtendedFlag::SKIP_WORKTREE.bits(),
+        path: path.as_bytes().to_vec(),
+      })?,
+      Err(err) if err.code() == ErrorCode::NotFound => {
+        if index.get_path(Path::new(path), 0).is_some() {
+          index.remove_path(Path::new(path))?;
+        }
+      }
+      Err(err) => return Err(err.into()),
+    }
+  }
+  index.write().map_err(GitError::from)
 }
 
-#[tauri::command]
-pub fn git_branch_exists(repo_path: String, branch: String) -> Result<bool, String> {
-  validate_git_ref(&branch)?;
-  with_cached_repo(&repo_path, |repo| {
-    let ref_name = format!("refs/heads/{}", branch);
-    let exists = repo.find_reference(&ref_name).is_ok();
-    Ok(exists)
+fn conflict_side(repo: &Repository, entry: &IndexEntry) -> Result<GitConflictSide, GitError> {
+  let blob = repo.find_blob(entry.id)?;
+  let binary = blob.is_binary();
+  Ok(GitConflictSide {
+    oid: entry.id.to_string(),
+    mode: entry.mode,
+    binary,
+    content: (!binary).then(|| String::from_utf8_lossy(blob.content()).to_string()),
   })
 }
 
-#[tauri::command]
-pub fn git_create_branch(
-  repo_path: String,
-  branch: String,
-  start_point: Option<String>,
-) -> Result<(), String> {
-  validate_git_ref(&branch)?;
-  if let Some(ref sp) = start_point {
-    validate_git_ref(sp)?;
+/// Merges the ours/theirs stages with diff3-style markers. A missing ancestor
+/// (both sides added the path) is merged against an empty file.
+fn merged_conflict_text(
+  repo: &Repository,
+  conflict: &git2::IndexConflict,
+) -> Result<Option<String>, GitError> {
+  let (ours, theirs) = match (&conflict.our, &conflict.their) {
+    (Some(ours), Some(theirs)) => (ours, theirs),
+    _ => return Ok(None),
+  };
+  if repo.find_blob(ours.id)?.is_binary() || repo.find_blob(theirs.id)?.is_binary() {
+    return Ok(None);
   }
-  let repo = open_repo_at(&repo_path)?;
-  let commit = match start_point {
-    Some(spec) => resolve_commit(&repo, &spec)?,
+  let empty;
+  let ancestor = match &conflict.ancestor {
+    Some(entry) => entry,
     None => {
-      let head = repo.head().map_err(|err| err.to_string())?;
-      head
-        .peel(ObjectType::Commit)
-        .map_err(|err| err.to_string())?
-        .into_commit()
-        .map_err(|_| "invalid commit".to_string())?
+      empty = IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode: ours.mode,
+        uid: 0,
+        gid: 0,
+        file_size: 0,
+        id: repo.blob(&[])?,
+        flags: 0,
+        flags_extended: 0,
+        path: ours.path.clone(),
+      };
+      &empty
     }
   };
-  repo
-    .branch(&branch, &commit, false)
-    .map(|_| ())
-    .map_err(|err| err.to_string())
+  let mut opts = MergeFileOptions::new();
+  opts
+    .ancestor_label("base")
+    .our_label("ours")
+    .their_label("theirs")
+    .style_diff3(true);
+  let result = repo.merge_file_from_index(ancestor, ours, theirs, Some(&mut opts))?;
+  Ok(Some(String::from_utf8_lossy(result.content()).to_string()))
 }
 
-#[tauri::command]
-pub fn git_list_worktrees(repo_path: String) -> Result<Vec<WorktreeInfo>, String> {
-  let repo = open_repo_at(&repo_path)?;
-  let mut worktrees = Vec::new();
+/// Joins a repo-relative path onto the worktree, rejecting anything that could
+/// escape it.
+fn worktree_file(repo: &Repository, path: &str) -> Result<PathBuf, GitError> {
+  Ok(repo_workdir(repo)?.join(repo_relative_path(path)?))
+}
 
-  if let Ok(workdir) = repo_workdir(&repo) {
-    worktrees.push(worktree_info_for_path(workdir, false, false)?);
+/// Checks that `path` is a plain repo-relative path with no `..`, root or
+/// prefix components.
+pub(crate) fn repo_relative_path(path: &str) -> Result<&Path, GitError> {
+  let relative = Path::new(path);
+  let escapes = relative
+    .components()
+    .any(|component| !matches!(component, std::path::Component::Normal(_)));
+  if path.is_empty() || escapes {
+    return Err(GitError::new(
+      GitErrorCode::InvalidArgument,
+      format!("invalid path: {}", path),
+    ));
   }
+  Ok(relative)
+}
 
-  let names = repo.worktrees().map_err(|err| err.to_string())?;
-  for name in names.iter().flatten() {
-    let worktree = repo.find_worktree(name).map_err(|err| err.to_string())?;
-    let locked = matches!(worktree.is_locked(), Ok(WorktreeLockStatus::Locked(_)));
-    let mut prune_opts = WorktreePruneOptions::new();
-    let prunable = worktree.is_prunable(Some(&mut prune_opts)).unwrap_or(false);
-    worktrees.push(worktree_info_for_path(worktree.path(), locked, prunable)?);
+fn tree_entry_by_path(tree: &git2::Tree, path: &str) -> Result<git2::TreeEntry<'static>, GitError> {
+  tree.get_path(repo_relative_path(path)?).map_err(|err| {
+    if err.code() == ErrorCode::NotFound {
+      GitError::new(GitErrorCode::PathNotFound, format!("{} not found", path))
+    } else {
+      err.into()
+    }
+  })
+}
+
+fn tree_entry_kind(kind: Option<ObjectType>) -> GitTreeEntryKind {
+  match kind {
+    Some(ObjectType::Tree) => GitTreeEntryKind::Tree,
+    Some(ObjectType::Commit) => GitTreeEntryKind::Submodule,
+    _ => GitTreeEntryKind::Blob,
   }
+}
 
-  Ok(worktrees)
+#[cfg(unix)]
+fn set_executable(path: &Path, executable: bool) -> Result<(), GitError> {
+  use std::os::unix::fs::PermissionsExt;
+  let mode = if executable { 0o755 } else { 0o644 };
+  std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).map_err(GitError::from)
 }
 
-#[tauri::command]
-pub fn git_create_worktree(
-  repo_path: String,
+#[cfg(not(unix))]
+fn set_executable(_path: &Path, _executable: bool) -> Result<(), GitError> {
+  Ok(())
+}
+
+#[cfg(unix)]
+fn write_symlink(path: &Path, target: &[u8]) -> Result<(), GitError> {
+  use std::os::unix::ffi::OsStrExt;
+  std::os::unix::fs::symlink(std::ffi::OsStr::from_bytes(target), path).map_err(GitError::from)
+}
+
+/// Like git with `core.symlinks=false`, the link target is written as a file.
+#[cfg(not(unix))]
+fn write_symlink(path: &Path, target: &[u8]) -> Result<(), GitError> {
+  std::fs::write(path, target).map_err(GitError::from)
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+  use std::os::unix::fs::PermissionsExt;
+  std::fs::metadata(path)
+    .map(|metadata| metadata.permissions().mode() & 0o111 != 0)
+    .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(_path: &Path) -> bool {
+  false
+}
+
+/// Fails if the index has conflicts or staged changes, which an operation that
+/// writes its own commit would otherwise pick up or discard.
+fn ensure_index_matches_head(repo: &Repository) -> Result<(), GitError> {
+  let index = repo.index()?;
+  if index.has_conflicts() {
+    return Err(GitError::new(GitErrorCode::Conflict, "index has unresolved conflicts"));
+  }
+  let head_tree = head_commit(repo)?.map(|commit| commit.tree()).transpose()?;
+  let staged = repo.diff_tree_to_index(head_tree.as_ref(), Some(&index), None)?;
+  if staged.deltas().len() > 0 {
+    return Err(GitError::new(GitErrorCode::DirtyWorktree, "index has staged changes"));
+  }
+  Ok(())
+}
+
+/// Points HEAD (or the branch it refers to, even if unborn) at `oid`.
+fn move_head(repo: &Repository, oid: Oid, log_message: &str) -> Result<(), GitError> {
+  let head = repo.find_reference("HEAD")?;
+  match head.symbolic_target() {
+    Some(name) => repo.reference(name, oid, true, log_message).map(|_| ()),
+    None => repo.set_head_detached(oid),
+  }
+  .map_err(GitError::from)
+}
+
+/// Applies rebase operations until one conflicts or none are left. Operations
+/// whose changes are already upstream are dropped, like `git rebase` does.
+fn run_rebase(
+  repo: &Repository,
+  rebase: &mut git2::Rebase,
+  committer: &Signature,
+) -> Result<GitRebaseResult, GitError> {
+  while let Some(operation) = rebase.next() {
+    let operation = operation?;
+    let index = repo.index()?;
+    if index.has_conflicts() {
+      return Ok(GitRebaseResult {
+        status: GitRebaseStatus::Conflicts,
+        head: None,
+        current: Some(operation.id().to_string()),
+        step: rebase.operation_current().map(|idx| idx + 1).unwrap_or(0),
+        total: rebase.len(),
+        conflicts: conflict_paths(&index)?,
+      });
+    }
+    commit_rebase_operation(rebase, committer)?;
+  }
+  let total = rebase.len();
+  rebase.finish(Some(committer))?;
+  Ok(GitRebaseResult {
+    status: GitRebaseStatus::Completed,
+    head: Some(head_oid_string(repo)),
+    current: None,
+    step: total,
+    total,
+    conflicts: Vec::new(),
+  })
+}
+
+fn commit_rebase_operation(
+  rebase: &mut git2::Rebase,
+  committer: &Signature,
+) -> Result<(), GitError> {
+  match rebase.commit(None, committer, None) {
+    Err(err) if err.code() == ErrorCode::Applied => Ok(()),
+    result => result.map(|_| ()).map_err(GitError::from),
+  }
+}
+
+/// File in the worktree's git dir holding a stopped cherry-pick's progress,
+/// next to git's own CHERRY_PICK_HEAD and MERGE_MSG.
+const CHERRY_PICK_STATE: &str = "FORKS_CHERRY_PICK";
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CherryPickState {
+  /// HEAD before the sequence started, restored by abort.
+  orig_head: String,
+  mainline: Option<u32>,
+  /// Commits still to be picked after the stopped one.
+  remaining: Vec<String>,
+}
+
+fn read_cherry_pick_state(repo: &Repository) -> Result<Option<CherryPickState>, GitError> {
+  match std::fs::read_to_string(repo.path().join(CHERRY_PICK_STATE)) {
+    Ok(content) => serde_json::from_str(&content).map(Some).map_err(|err| {
+      GitError::new(GitErrorCode::Other, format!("invalid cherry-pick state: {}", err))
+    }),
+    Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+    Err(err) => Err(err.into()),
+  }
+}
+
+/// Removes CHERRY_PICK_HEAD, MERGE_MSG and the recorded progress.
+fn clear_cherry_pick_state(repo: &Repository) -> Result<(), GitError> {
+  repo.cleanup_state()?;
+  match std::fs::remove_file(repo.path().join(CHERRY_PICK_STATE)) {
+    Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+    _ => Ok(()),
+  }
+}
+
+/// Picks `commits` onto HEAD in order, adding to `result`. A commit that
+/// conflicts stops the sequence: its conflicts are checked out and recorded the
+/// way `git cherry-pick` does, with the rest of `commits` saved for continue.
+fn run_cherry_pick(
+  repo: &Repository,
+  commits: &[Oid],
+  mainline: Option<u32>,
+  orig_head: Oid,
+  result: &mut GitCherryPickResult,
+) -> Result<(), GitError> {
+  let committer = signature_or_default(repo)?;
+  for (idx, oid) in commits.iter().enumerate() {
+    let commit = repo.find_commit(*oid)?;
+    let head = head_commit(repo)?
+      .ok_or_else(|| GitError::new(GitErrorCode::RefNotFound, "HEAD is unborn"))?;
+    if commit.parent_count() > 1 && mainline.is_none() {
+      return Err(GitError::new(
+        GitErrorCode::InvalidArgument,
+        format!("{} is a merge commit; `mainline` is required", commit.id()),
+      ));
+    }
+    let remaining: Vec<String> = commits[idx + 1..].iter().map(Oid::to_string).collect();
+    let mut index = repo.cherrypick_commit(&commit, &head, mainline.unwrap_or(0), None)?;
+    let conflicts = if index.has_conflicts() {
+      let diff = repo.diff_tree_to_index(Some(&head.tree()?), Some(&index), None)?;
+      let dirty = dirty_paths_in_diff(repo, &diff)?;
+      if dirty.is_empty() {
+        let mut checkout = CheckoutBuilder::new();
+        checkout.safe().allow_conflicts(true).conflict_style_merge(true);
+        repo.checkout_index(Some(&mut index), Some(&mut checkout))?;
+        let state = CherryPickState {
+          orig_head: orig_head.to_string(),
+          mainline,
+          remaining: remaining.clone(),
+        };
+        let state = serde_json::to_string(&state)
+          .map_err(|err| GitError::new(GitErrorCode::Other, err.to_string()))?;
+        std::fs::write(repo.path().join(CHERRY_PICK_STATE), state)?;
+        std::fs::write(repo.path().join("CHERRY_PICK_HEAD"), format!("{}\n", commit.id()))?;
+        std::fs::write(repo.path().join("MERGE_MSG"), commit.message_bytes())?;
+        conflict_paths(&index)?
+      } else {
+        dirty
+      }
+    } else {
+      let tree = repo.find_tree(index.write_tree_to(repo)?)?;
+      if tree.id() == head.tree_id() {
+        result.skipped.push(commit.id().to_string());
+        continue;
+      }
+      let conflicts = checkout_conflicts(repo, &tree)?;
+      if conflicts.is_empty() {
+        checkout_tree_safe(repo, &tree)?;
+        let oid = repo.commit(
+          Some("HEAD"),
+          &commit.author(),
+          &committer,
+          &String::from_utf8_lossy(commit.message_bytes()),
+          &tree,
+          &[&head],
+        )?;
+        result.picked.push(oid.to_string());
+        continue;
+      }
+      conflicts
+    };
+    result.status = GitCherryPickStatus::Conflicts;
+    result.failed = Some(commit.id().to_string());
+    result.conflicts = conflicts;
+    result.remaining = remaining;
+    break;
+  }
+  Ok(())
+}
+
+/// Worktree file and stage-0 index entry of a locally changed path.
+struct LocalChange {
   path: String,
+  content: Option<Vec<u8>>,
+  executable: bool,
+  entry: Option<IndexEntry>,
+}
+
+/// Snapshots local changes to paths outside `pathspec`.
+fn local_changes_outside(
+  repo: &Repository,
+  pathspec: &Pathspec,
+) -> Result<Vec<LocalChange>, GitError> {
+  let workdir = repo_workdir(repo)?;
+  let index = repo.index()?;
+  let mut options = git2::StatusOptions::new();
+  options.include_untracked(true).recurse_untracked_dirs(true);
+  let mut changes = Vec::new();
+  let skipped = skip_worktree_paths(&index);
+  for entry in repo.statuses(Some(&mut options))?.iter() {
+    if is_sparse_deletion(entry.status(), entry.path(), &skipped) {
+      continue;
+    }
+    let path = match entry.path() {
+      Some(value) => value.to_string(),
+      None => continue,
+    };
+    if pathspec.matches_path(Path::new(&path), PathspecFlags::DEFAULT) {
+      continue;
+    }
+    let file = workdir.join(&path);
+    let content = match std::fs::read(&file) {
+      Ok(bytes) => Some(bytes),
+      Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+      Err(err) => return Err(err.into()),
+    };
+    changes.push(LocalChange {
+      executable: is_executable(&file),
+      entry: index.get_path(Path::new(&path), 0),
+      content,
+      path,
+    });
+  }
+  Ok(changes)
+}
+
+fn restore_local_change(
+  workdir: &Path,
+  index: &mut git2::Index,
+  change: &LocalChange,
+) -> Result<(), GitError> {
+  let file = workdir.join(&change.path);
+  match change.content {
+    Some(ref bytes) => {
+      if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent)?;
+      }
+      std::fs::write(&file, bytes)?;
+      set_executable(&file, change.executable)?;
+    }
+    None if file.exists() => std::fs::remove_file(&file)?,
+    None => {}
+  }
+  match change.entry {
+    Some(ref entry) => index.add(entry)?,
+    None => index.remove_path(Path::new(&change.path))?,
+  }
+  Ok(())
+}
+
+/// Puts back changes taken by `local_changes_outside`. One path failing doesn't
+/// stop the others; the ones that couldn't be restored are listed in the error.
+fn restore_local_changes(repo: &Repository, changes: Vec<LocalChange>) -> Result<(), GitError> {
+  let workdir = repo_workdir(repo)?;
+  let mut index = repo.index()?;
+  let mut failed = Vec::new();
+  for change in changes {
+    if let Err(err) = restore_local_change(workdir, &mut index, &change) {
+      eprintln!("[git] could not restore {}: {}", change.path, err);
+      failed.push(change.path);
+    }
+  }
+  index.write()?;
+  if failed.is_empty() {
+    return Ok(());
+  }
+  Err(
+    GitError::new(GitErrorCode::Other, "could not restore local changes")
+      .with_details(serde_json::json!({ "paths": failed })),
+  )
+}
+
+fn stash_apply_options(reinstate_index: Option<bool>) -> StashApplyOptions<'static> {
+  let mut options = StashApplyOptions::new();
+  if reinstate_index.unwrap_or(false) {
+    options.reinstantiate_index();
+  }
+  options
+}
+
+fn to_signature(value: &GitSignature) -> Result<Signature<'static>, GitError> {
+  Signature::now(&value.name, &value.email).map_err(GitError::from)
+}
+
+/// The configured identity, or a placeholder so operations don't fail just
+/// because no `user.name`/`user.email` is set.
+pub(crate) fn signature_or_default(repo: &Repository) -> Result<Signature<'static>, GitError> {
+  repo
+    .signature()
+    .or_else(|_| Signature::now("forks", "forks@localhost"))
+    .map_err(GitError::from)
+}
+
+fn ensure_clean_worktree(path: &Path) -> Result<(), GitError> {
+  let repo = Repository::open(path)?;
+  let statuses = repo.statuses(None)?;
+  let skipped = skip_worktree_paths(&repo.index()?);
+  let clean = statuses
+    .iter()
+    .all(|entry| is_sparse_deletion(entry.status(), entry.path(), &skipped));
+  if clean {
+    Ok(())
+  } else {
+    Err(GitError::new(
+      GitErrorCode::DirtyWorktree,
+      "worktree has uncommitted changes",
+    ))
+  }
+}
+
+fn status_to_kind(status: git2::Status) -> Option<String> {
+  if status.is_conflicted() {
+    return Some("conflicted".to_string());
+  }
+  if status.is_index_deleted() || status.is_wt_deleted() {
+    return Some("deleted".to_string());
+  }
+  if status.is_index_new() {
+    return Some("added".to_string());
+  }
+  if status.is_wt_new() {
+    return Some("untracked".to_string());
+  }
+  if status.is_index_renamed() || status.is_wt_renamed() {
+    return Some("renamed".to_string());
+  }
+  if status.is_index_typechange() || status.is_wt_typechange() {
+    return Some("typechange".to_string());
+  }
+  if status.is_index_modified() || status.is_wt_modified() {
+    return Some("modified".to_string());
+  }
+  if status.is_ignored() {
+    return Some("ignored".to_string());
+  }
+  None
+}
+
+fn index_status_kind(status: git2::Status) -> Option<String> {
+  let kind = if status.is_conflicted() {
+    "conflicted"
+  } else if status.is_index_new() {
+    "added"
+  } else if status.is_index_deleted() {
+    "deleted"
+  } else if status.is_index_renamed() {
+    "renamed"
+  } else if status.is_index_typechange() {
+    "typechange"
+  } else if status.is_index_modified() {
+    "modified"
+  } else {
+    return None;
+  };
+  Some(kind.to_string())
+}
+
+fn worktree_status_kind(status: git2::Status) -> Option<String> {
+  let kind = if status.is_conflicted() {
+    "conflicted"
+  } else if status.is_wt_new() {
+    "untracked"
+  } else if status.is_wt_deleted() {
+    "deleted"
+  } else if status.is_wt_renamed() {
+    "renamed"
+  } else if status.is_wt_typechange() {
+    "typechange"
+  } else if status.is_wt_modified() {
+    "modified"
+  } else if status.is_ignored() {
+    "ignored"
+  } else {
+    return None;
+  };
+  Some(kind.to_string())
+}
+
+#[tauri::command]
+pub fn git_is_repo(path: String) -> Result<bool, GitError> {
+  Ok(open_repo(&path).is_ok())
+}
+
+#[tauri::command]
+pub fn git_repo_root(path: String) -> Result<String, GitError> {
+  let repo = open_repo(&path)?;
+  let workdir = repo_workdir(&repo)?;
+  Ok(workdir.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn git_default_branch(repo_path: String) -> Result<String, GitError> {
+  with_cached_repo(&repo_path, |repo| {
+    if let Ok(reference) = repo.find_reference("refs/remotes/origin/HEAD") {
+      if let Some(target) = reference.symbolic_target() {
+        if let Some(stripped) = target.strip_prefix("refs/remotes/origin/") {
+          return Ok(stripped.to_string());
+        }
+        return Ok(target.to_string());
+      }
+    }
+    if let Some(branch) = branch_from_head(repo) {
+      return Ok(branch);
+    }
+    Ok("main".to_string())
+  })
+}
+
+#[tauri::command]
+pub fn git_current_branch(path: String) -> Result<String, GitError> {
+  with_cached_repo(&path, |repo| {
+    Ok(branch_from_head(repo).unwrap_or_default())
+  })
+}
+
+#[tauri::command]
+pub fn git_branch_exists(repo_path: String, branch: String) -> Result<bool, GitError> {
+  validate_git_ref(&branch)?;
+  with_cached_repo(&repo_path, |repo| {
+    let ref_name = format!("refs/heads/{}", branch);
+    let exists = repo.find_reference(&ref_name).is_ok();
+    Ok(exists)
+  })
+}
+
+/// Maps each branch ref checked out somewhere to the worktree holding it,
+/// covering the main worktree and every linked one.
+fn checked_out_branches(repo: &Repository) -> Result<HashMap<String, String>, GitError> {
+  let mut repos = vec![Repository::open(repo.commondir())?];
+  let names = repo.worktrees()?;
+  for name in names.iter().flatten() {
+    // Worktrees whose directory is gone can't hold anything checked out.
+    if let Ok(linked) = repo
+      .find_worktree(name)
+      .and_then(|worktree| Repository::open_from_worktree(&worktree))
+    {
+      repos.push(linked);
+    }
+  }
+  let mut branches = HashMap::new();
+  for repo in repos {
+    let workdir = match repo.workdir() {
+      Some(value) => value.to_string_lossy().trim_end_matches('/').to_string(),
+      None => continue,
+    };
+    if let Some(target) = repo.find_reference("HEAD")?.symbolic_target() {
+      branches.insert(target.to_string(), workdir);
+    }
+  }
+  Ok(branches)
+}
+
+pub(crate) fn ensure_not_checked_out(repo: &Repository, ref_name: &str) -> Result<(), GitError> {
+  match checked_out_branches(repo)?.get(ref_name) {
+    Some(path) => Err(
+      GitError::new(GitErrorCode::BranchCheckedOut, "branch is checked out in a worktree")
+        .with_details(serde_json::json!({ "worktreePath": path })),
+    ),
+    None => Ok(()),
+  }
+}
+
+/// Lists branches, optionally only local or remote ones. `pattern` matches the
+/// short name and may use `*` and `?`; without either it is a prefix, so
+/// `attempt/` and `attempt/*` are the same.
+#[tauri::command]
+pub fn git_list_branches(
+  repo_path: String,
+  kind: Option<GitBranchKind>,
+  pattern: Option<String>,
+) -> Result<Vec<GitBranchInfo>, GitError> {
+  let pattern = pattern.filter(|value| !value.is_empty());
+  if let Some(ref value) = pattern {
+    validate_git_ref(&value.trim_end_matches('/').replace(['*', '?'], "x"))?;
+  }
+  let glob = match pattern {
+    Some(value) if value.contains(['*', '?']) => value,
+    Some(value) => format!("{}*", value),
+    None => "*".to_string(),
+  };
+  let repo = open_repo_at(&repo_path)?;
+  let checked_out = checked_out_branches(&repo)?;
+  let kinds = match kind {
+    Some(value) => vec![value],
+    None => vec![GitBranchKind::Local, GitBranchKind::Remote],
+  };
+  let mut branches = Vec::new();
+  for kind in kinds {
+    let prefix = match kind {
+      GitBranchKind::Local => "refs/heads/",
+      GitBranchKind::Remote => "refs/remotes/",
+    };
+    let start = branches.len();
+    for reference in repo.references_glob(&format!("{}{}", prefix, glob))? {
+      let reference = reference?;
+      // Skips symbolic refs such as refs/remotes/origin/HEAD.
+      let (Some(ref_name), Some(oid)) = (reference.name(), reference.target()) else {
+        continue;
+      };
+      let ref_name = ref_name.to_string();
+      let name = ref_name[prefix.len()..].to_string();
+      let commit = repo.find_commit(oid)?;
+      let (upstream, ahead, behind) = match kind {
+        GitBranchKind::Local => upstream_tracking(&repo, &name, Some(oid))?,
+        GitBranchKind::Remote => (None, 0, 0),
+      };
+      branches.push(GitBranchInfo {
+        name,
+        kind,
+        oid: oid.to_string(),
+        upstream,
+        ahead,
+        behind,
+        commit_time: commit.time().seconds(),
+        summary: commit.summary().unwrap_or_default().to_string(),
+        worktree_path: checked_out.get(&ref_name).cloned(),
+      });
+    }
+    branches[start..].sort_by(|a, b| a.name.cmp(&b.name));
+  }
+  Ok(branches)
+}
+
+#[tauri::command]
+pub fn git_create_branch(
+  repo_path: String,
   branch: String,
-  create_branch: bool,
-) -> Result<(), String> {
+  start_point: Option<String>,
+) -> Result<(), GitError> {
   validate_git_ref(&branch)?;
+  if let Some(ref sp) = start_point {
+    validate_git_ref(sp)?;
+  }
+  let repo = open_repo_at(&repo_path)?;
+  create_branch_at(&repo, &branch, start_point.as_deref())
+}
+
+/// Lists the main worktree and every linked one. With `include_status`, each
+/// existing worktree also reports whether it has changes and how many paths.
+#[tauri::command]
+pub fn git_list_worktrees(
+  repo_path: String,
+  include_status: Option<bool>,
+) -> Result<Vec<WorktreeInfo>, GitError> {
+  let include_status = include_status.unwrap_or(false);
+  let repo = open_repo_at(&repo_path)?;
+  let mut worktrees = Vec::new();
+
+  if let Ok(workdir) = repo_workdir(&repo) {
+    let main = Repository::open(workdir)?;
+    worktrees.push(worktree_info(&main, workdir, include_status)?);
+  }
+
+  let names = repo.worktrees()?;
+  for name in names.iter().flatten() {
+    let worktree = repo.find_worktree(name)?;
+    // Worktrees whose path is gone can't be opened normally; their admin dir
+    // still has HEAD, so open that without a workdir.
+    let linked = match Repository::open_from_worktree(&worktree) {
+      Ok(value) => value,
+      Err(_) => Repository::open_bare(repo.commondir().join("worktrees").join(name))?,
+    };
+    let mut info = worktree_info(&linked, worktree.path(), include_status)?;
+    info.name = Some(name.to_string());
+    info.bare = false;
+    if let Ok(WorktreeLockStatus::Locked(reason)) = worktree.is_locked() {
+      info.locked = true;
+      info.lock_reason = reason.filter(|value| !value.trim().is_empty());
+    }
+    let mut prune_opts = WorktreePruneOptions::new();
+    info.prunable = worktree.is_prunable(Some(&mut prune_opts)).unwrap_or(false);
+    worktrees.push(info);
+  }
+
+  Ok(worktrees)
+}
+
+/// What a new worktree's HEAD should point at.
+pub(crate) enum WorktreeHead {
+  /// Full ref name, e.g. `refs/heads/main`.
+  Branch(String),
+  Detached(Oid),
+}
+
+/// Validates the branch and start point for a new worktree and resolves its
+/// HEAD, creating the branch first with `create_branch`.
+pub(crate) fn prepare_worktree_head(
+  repo: &Repository,
+  branch: Option<&str>,
+  create_branch: bool,
+  options: &GitWorktreeOptions,
+) -> Result<WorktreeHead, GitError> {
+  if let Some(value) = branch {
+    validate_git_ref(value)?;
+  }
+  let start_point = options.start_point.as_deref();
+  if let Some(value) = start_point {
+    validate_revspec(value)?;
+  }
+  if options.detached {
+    if create_branch {
+      return Err(GitError::new(
+        GitErrorCode::InvalidArgument,
+        "cannot create a branch for a detached worktree",
+      ));
+    }
+    let commit = match start_point.or(branch) {
+      Some(spec) => resolve_commit(repo, spec)?,
+      None => head_commit(repo)?
+        .ok_or_else(|| GitError::new(GitErrorCode::RefNotFound, "HEAD is unborn"))?,
+    };
+    return Ok(WorktreeHead::Detached(commit.id()));
+  }
+  let branch = branch.ok_or_else(|| {
+    GitError::new(GitErrorCode::InvalidArgument, "branch is required unless detached")
+  })?;
+  if create_branch {
+    create_branch_at(repo, branch, start_point)?;
+  } else if start_point.is_some() {
+    return Err(GitError::new(
+      GitErrorCode::InvalidArgument,
+      "start point requires create_branch or detached",
+    ));
+  }
+  Ok(WorktreeHead::Branch(format!("refs/heads/{}", branch)))
+}
+
+/// Adds a worktree at `path` with `branch` checked out, creating the branch at
+/// the start point (default HEAD) with `create_branch`. See
+/// `GitWorktreeOptions` for detached, empty, sparse and locked worktrees.
+#[tauri::command]
+pub fn git_create_worktree(
+  repo_path: String,
+  path: String,
+  branch: Option<String>,
+  create_branch: bool,
+  options: Option<GitWorktreeOptions>,
+) -> Result<(), GitError> {
+  let options = options.unwrap_or_default();
   let repo = open_repo_at(&repo_path)?;
   let path_buf = PathBuf::from(&path);
+  let sparse_dirs = options.sparse_paths.as_deref().map(normalize_sparse_dirs).transpose()?;
+  let head = prepare_worktree_head(&repo, branch.as_deref(), create_branch, &options)?;
+
+  let name = worktree_name_from_path(&path_buf, branch.as_deref().unwrap_or("detached"));
+  match head {
+    WorktreeHead::Branch(ref_name) if !options.no_checkout && sparse_dirs.is_none() => {
+      let reference = repo.find_reference(&ref_name)?;
+      let mut opts = WorktreeAddOptions::new();
+      opts.reference(Some(&reference));
+      repo.worktree(&name, &path_buf, Some(&mut opts))?;
+    }
+    head => {
+      let head_file = match head {
+        WorktreeHead::Branch(ref_name) => {
+          ensure_not_checked_out(&repo, &ref_name)?;
+          format!("ref: {}", ref_name)
+        }
+        WorktreeHead::Detached(oid) => oid.to_string(),
+      };
+      add_worktree_unpopulated(&repo, &name, &path_buf, &head_file, |worktree_repo| {
+        if let Some(dirs) = &sparse_dirs {
+          write_sparse_checkout(worktree_repo, dirs)?;
+          if !options.no_checkout {
+            apply_sparse_checkout(worktree_repo, Some(dirs))?;
+          }
+        } else if !options.no_checkout {
+          let mut checkout = CheckoutBuilder::new();
+          checkout.force();
+          worktree_repo.checkout_head(Some(&mut checkout))?;
+        }
+        Ok(())
+      })?;
+    }
+  }
+
+  if options.lock || options.lock_reason.is_some() {
+    repo.find_worktree(&name)?.lock(options.lock_reason.as_deref())?;
+  }
+  Ok(())
+}
+
+#[tauri::command]
+pub fn git_remove_worktree(
+  worktree_path: String,
+  force: Option<bool>,
+) -> Result<(), GitError> {
+  let path = PathBuf::from(&worktree_path);
+  let repo = open_repo(&worktree_path)?;
+  let worktree = Worktree::open_from_repository(&repo)?;
+
+  if !force.unwrap_or(false) {
+    ensure_clean_worktree(&path)?;
+  }
+
+  let mut prune_opts = WorktreePruneOptions::new();
+  prune_opts.valid(true);
+  prune_opts.working_tree(true);
+  if force.unwrap_or(false) {
+    prune_opts.locked(true);
+  }
+
+  worktree
+    .prune(Some(&mut prune_opts))
+    .map(|_| ())
+    .map_err(GitError::from)
+}
+
+fn open_linked_worktree(worktree_path: &str) -> Result<(Repository, Worktree), GitError> {
+  let repo = open_repo(worktree_path)?;
+  if !repo.is_worktree() {
+    return Err(GitError::new(
+      GitErrorCode::InvalidArgument,
+      "not a linked worktree",
+    ));
+  }
+  let worktree = Worktree::open_from_repository(&repo)?;
+  Ok((repo, worktree))
+}
+
+/// Reads a `gitdir: <path>` link file as found in a linked worktree's `.git`.
+fn read_gitdir_link(file: &Path) -> Result<PathBuf, GitError> {
+  let content = std::fs::read_to_string(file)?;
+  content
+    .trim_end()
+    .strip_prefix("gitdir: ")
+    .map(PathBuf::from)
+    .ok_or_else(|| {
+      GitError::new(
+        GitErrorCode::InvalidArgument,
+        format!("{} is not a gitdir link", file.display()),
+      )
+    })
+}
+
+/// Writes `content` unless the file already holds it; returns whether it wrote.
+fn write_if_changed(file: &Path, content: &str) -> Result<bool, GitError> {
+  if std::fs::read_to_string(file).ok().as_deref() == Some(content) {
+    return Ok(false);
+  }
+  std::fs::write(file, content)?;
+  Ok(true)
+}
+
+#[tauri::command]
+pub fn git_lock_worktree(
+  worktree_path: String,
+  reason: Option<String>,
+) -> Result<(), GitError> {
+  let (_, worktree) = open_linked_worktree(&worktree_path)?;
+  worktree.lock(reason.as_deref()).map_err(GitError::from)
+}
+
+#[tauri::command]
+pub fn git_unlock_worktree(worktree_path: String) -> Result<(), GitError> {
+  let (_, worktree) = open_linked_worktree(&worktree_path)?;
+  worktree.unlock().map_err(GitError::from)
+}
+
+/// Moves a linked worktree to `new_path`, creating its parent directories, and
+/// points its admin dir at the new location. The admin dir is renamed after the
+/// new directory, as `git_create_worktree` would have named it, unless that name
+/// is taken. Locked worktrees are only moved with `force`; moves across
+/// filesystems are rejected.
+#[tauri::command]
+pub fn git_move_worktree(
+  worktree_path: String,
+  new_path: String,
+  force: Option<bool>,
+) -> Result<(), GitError> {
+  let (repo, worktree) = open_linked_worktree(&worktree_path)?;
+  if !force.unwrap_or(false)
+    && matches!(worktree.is_locked()?, WorktreeLockStatus::Locked(_))
+  {
+    return Err(GitError::new(GitErrorCode::Locked, "worktree is locked"));
+  }
+  let new_path = PathBuf::from(&new_path);
+  if new_path.exists() {
+    return Err(GitError::new(GitErrorCode::Exists, "destination already exists"));
+  }
+  if let Some(parent) = new_path.parent() {
+    std::fs::create_dir_all(parent)?;
+  }
+  // A rename moves the whole tree or nothing, which a copy can't promise.
+  std::fs::rename(worktree.path(), &new_path).map_err(|err| {
+    if err.kind() == std::io::ErrorKind::CrossesDevices {
+      GitError::new(
+        GitErrorCode::InvalidArgument,
+        "cannot move a worktree across filesystems",
+      )
+    } else {
+      err.into()
+    }
+  })?;
+  let new_path = std::fs::canonicalize(&new_path)?;
+  // For a linked worktree the repository path is its admin dir.
+  let mut admin_dir = repo.path().to_path_buf();
+  let name = worktree_name_from_path(&new_path, worktree.name().unwrap_or("worktree"));
+  if worktree.name() != Some(name.as_str()) {
+    let renamed = repo.commondir().join("worktrees").join(&name);
+    if !renamed.exists() {
+      match std::fs::rename(&admin_dir, &renamed) {
+        Ok(()) => {
+          admin_dir = renamed;
+          std::fs::write(
+            new_path.join(".git"),
+            format!("gitdir: {}\n", admin_dir.display()),
+          )?;
+        }
+        Err(err) => eprintln!("[git] could not rename worktree {}: {}", name, err),
+      }
+    }
+  }
+  std::fs::write(
+    admin_dir.join("gitdir"),
+    format!("{}\n", new_path.join(".git").display()),
+  )?;
+  Ok(())
+}
+
+/// Repairs the links between worktrees and their admin dirs, like
+/// `git worktree repair`. Worktrees moved by hand are listed in `paths` so
+/// their admin dirs can be pointed at them; worktrees whose `.git` file still
+/// names the old repository location are found and fixed without help.
+/// Returns the worktree paths that were changed.
+#[tauri::command]
+pub fn git_repair_worktrees(
+  repo_path: String,
+  paths: Option<Vec<String>>,
+) -> Result<Vec<String>, GitError> {
+  let repo = open_repo_at(&repo_path)?;
+  let admin_root = std::fs::canonicalize(repo.commondir())?.join("worktrees");
+  let mut repaired = Vec::new();
+
+  for path in paths.unwrap_or_default() {
+    let path = std::fs::canonicalize(&path)?;
+    let dotgit = path.join(".git");
+    let linked = read_gitdir_link(&dotgit)?;
+    // The admin dir keeps its name when the whole repository moves.
+    let admin = match linked.file_name() {
+      Some(name) if !linked.is_dir() => admin_root.join(name),
+      _ => linked,
+    };
+    if !admin.join("commondir").is_file() {
+      return Err(GitError::new(
+        GitErrorCode::PathNotFound,
+        format!("no worktree admin dir for {}", path.display()),
+      ));
+    }
+    let mut changed = write_if_changed(&dotgit, &format!("gitdir: {}\n", admin.display()))?;
+    changed |= write_if_changed(
+      &admin.join("gitdir"),
+      &format!("{}\n", dotgit.display()),
+    )?;
+    if changed {
+      repaired.push(path.to_string_lossy().into_owned());
+    }
+  }
+
+  let common_dir = admin_root.parent().unwrap_or(&admin_root).to_path_buf();
+  let names = repo.worktrees()?;
+  for name in names.iter().flatten() {
+    let admin = admin_root.join(name);
+    let dotgit = match std::fs::read_to_string(admin.join("gitdir")) {
+      Ok(value) => PathBuf::from(value.trim_end()),
+      Err(_) => continue,
+    };
+    // libgit2 records `commondir` as an absolute path, which goes stale when
+    // the repository moves; git's relative form doesn't.
+    let common = std::fs::read_to_string(admin.join("commondir"))
+      .ok()
+      .and_then(|value| std::fs::canonicalize(admin.join(value.trim_end())).ok());
+    let mut changed = false;
+    if common.as_deref() != Some(common_dir.as_path()) {
+      std::fs::write(admin.join("commondir"), "../..\n")?;
+      changed = true;
+    }
+    if dotgit.is_file() {
+      let linked = read_gitdir_link(&dotgit)
+        .ok()
+        .and_then(|value| std::fs::canonicalize(value).ok());
+      if linked.as_deref() != Some(admin.as_path()) {
+        changed |= write_if_changed(&dotgit, &format!("gitdir: {}\n", admin.display()))?;
+      }
+    }
+    let path = dotgit.parent().unwrap_or(&dotgit).to_string_lossy().into_owned();
+    if changed && !repaired.contains(&path) {
+      repaired.push(path);
+    }
+  }
+  Ok(repaired)
+}
+
+/// Prunes admin entries of worktrees whose directory is gone. Locked worktrees
+/// are kept. With `dry_run` nothing is removed and the would-be-pruned entries
+/// are only reported.
+#[tauri::command]
+pub fn git_prune_worktrees(
+  repo_path: String,
+  dry_run: Option<bool>,
+) -> Result<Vec<GitPrunedWorktree>, GitError> {
+  let repo = open_repo_at(&repo_path)?;
+  let mut pruned = Vec::new();
+  let names = repo.worktrees()?;
+  for name in names.iter().flatten() {
+    let worktree = repo.find_worktree(name)?;
+    let mut prune_opts = WorktreePruneOptions::new();
+    if !worktree.is_prunable(Some(&mut prune_opts))? {
+      continue;
+    }
+    if !dry_run.unwrap_or(false) {
+      worktree.prune(Some(&mut prune_opts))?;
+    }
+    pruned.push(GitPrunedWorktree {
+      name: name.to_string(),
+      path: worktree.path().to_string_lossy().into_owned(),
+    });
+  }
+  Ok(pruned)
+}
+
+/// Sorts, dedupes and validates cone directories, dropping any nested in another.
+fn normalize_sparse_dirs(dirs: &[String]) -> Result<Vec<String>, GitError> {
+  let mut normalized = Vec::new();
+  for dir in dirs {
+    let dir = dir.trim_matches('/');
+    repo_relative_path(dir)?;
+    // These would need escaping in the pattern file.
+    if dir.contains(['*', '?', '[', '\\']) || dir.starts_with(['!', '#']) {
+      return Err(GitError::new(
+        GitErrorCode::InvalidArgument,
+        format!("unsupported sparse checkout path: {}", dir),
+      ));
+    }
+    normalized.push(dir.to_string());
+  }
+  normalized.sort();
+  normalized.dedup();
+  let mut kept: Vec<String> = Vec::new();
+  for dir in normalized {
+    if !kept.iter().any(|parent| dir.starts_with(&format!("{}/", parent))) {
+      kept.push(dir);
+    }
+  }
+  Ok(kept)
+}
+
+/// Whether `path` is checked out in a cone of `dirs`: files at the root, in an
+/// ancestor of a cone directory, or anywhere below one.
+pub(crate) fn in_sparse_cone(path: &str, dirs: &[String]) -> bool {
+  let parent = match path.rsplit_once('/') {
+    Some((parent, _)) => parent,
+    None => return true,
+  };
+  dirs.iter().any(|dir| {
+    path.starts_with(&format!("{}/", dir)) || dir.starts_with(&format!("{}/", parent))
+  })
+}
+
+/// Renders `dirs` as cone-mode patterns, the way `git sparse-checkout set` does.
+fn cone_patterns(dirs: &[String]) -> String {
+  let mut parents = std::collections::BTreeSet::new();
+  for dir in dirs {
+    let mut end = 0;
+    while let Some(offset) = dir[end..].find('/') {
+      end += offset;
+      parents.insert(&dir[..end]);
+      end += 1;
+    }
+  }
+  let mut entries: Vec<(&str, bool)> = parents
+    .into_iter()
+    .filter(|parent| !dirs.iter().any(|dir| dir == parent))
+    .map(|parent| (parent, false))
+    .chain(dirs.iter().map(|dir| (dir.as_str(), true)))
+    .collect();
+  entries.sort();
+  let mut patterns = String::from("/*\n!/*/\n");
+  for (dir, recursive) in entries {
+    patterns.push_str(&format!("/{}/\n", dir));
+    if !recursive {
+      patterns.push_str(&format!("!/{}/*/\n", dir));
+    }
+  }
+  patterns
+}
+
+fn read_sparse_checkout(repo: &Repository) -> Result<GitSparseCheckout, GitError> {
+  let config = repo.config()?;
+  let enabled = config.get_bool("core.sparseCheckout").unwrap_or(false);
+  let cone = enabled && config.get_bool("core.sparseCheckoutCone").unwrap_or(false);
+  let patterns: Vec<String> = std::fs::read_to_string(repo.path().join("info/sparse-checkout"))
+    .unwrap_or_default()
+    .lines()
+    .map(str::trim_end)
+    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    .map(str::to_string)
+    .collect();
+  let mut paths = Vec::new();
+  if cone {
+    let parents: HashSet<&str> = patterns
+      .iter()
+      .filter_map(|line| line.strip_prefix("!/")?.strip_suffix("/*/"))
+      .collect();
+    paths = patterns
+      .iter()
+      .filter_map(|line| line.strip_prefix('/')?.strip_suffix('/'))
+      .filter(|dir| *dir != "*" && !parents.contains(dir))
+      .map(str::to_string)
+      .collect();
+  }
+  Ok(GitSparseCheckout {
+    enabled,
+    cone,
+    paths,
+    patterns,
+  })
+}
+
+/// Cone directories of the worktree at `path`, or `None` unless it is a cone-mode
+/// sparse checkout.
+pub(crate) fn sparse_cone_dirs(path: &Path) -> Option<Vec<String>> {
+  let repo = Repository::open(path).ok()?;
+  let sparse = read_sparse_checkout(&repo).ok()?;
+  sparse.cone.then_some(sparse.paths)
+}
+
+/// Enables cone mode with `dirs` for the worktree of `repo`. Like git, the
+/// settings go to the per-worktree `config.worktree`, which needs
+/// `extensions.worktreeConfig` in the shared config.
+fn write_sparse_checkout(repo: &Repository, dirs: &[String]) -> Result<(), GitError> {
+  git2::Config::open(&repo.commondir().join("config"))?
+    .set_bool("extensions.worktreeConfig", true)?;
+  let mut config = git2::Config::open(&repo.path().join("config.worktree"))?;
+  config.set_bool("core.sparseCheckout", true)?;
+  config.set_bool("core.sparseCheckoutCone", true)?;
+  let info = repo.path().join("info");
+  std::fs::create_dir_all(&info)?;
+  std::fs::write(info.join("sparse-checkout"), cone_patterns(dirs))?;
+  Ok(())
+}
+
+/// Index paths flagged skip-worktree, i.e. left out of a sparse checkout.
+/// libgit2 ignores the flag and reports their missing files as deleted.
+fn skip_worktree_paths(index: &git2::Index) -> HashSet<PathBuf> {
+  index
+    .iter()
+    .filter(|entry| entry.flags_extended & IndexEntryExtendedFlag::SKIP_WORKTREE.bits() != 0)
+    .map(|entry| PathBuf::from(String::from_utf8_lossy(&entry.path).into_owned()))
+    .collect()
+}
+
+/// A missing file that is only missing because it's outside the sparse checkout.
+fn is_sparse_deletion(
+  status: git2::Status,
+  path: Option<&str>,
+  skipped: &HashSet<PathBuf>,
+) -> bool {
+  status == git2::Status::WT_DELETED && path.is_some_and(|path| skipped.contains(Path::new(path)))
+}
+
+/// Updates the index like `git add -A -- <paths>` (everything when empty), but
+/// keeps entries outside a sparse checkout instead of removing them because
+/// their files are missing.
+pub(crate) fn add_all_to_index(index: &mut git2::Index, paths: &[String]) -> Result<(), GitError> {
+  // git2 crashes on the NULL pathspec libgit2 reports to the callback when
+  // there are no pathspecs, so "everything" is spelled out.
+  let pathspecs = if paths.is_empty() { &["*".to_string()][..] } else { paths };
+  let skipped = skip_worktree_paths(index);
+  let mut keep_skipped = |path: &Path, _: &[u8]| i32::from(skipped.contains(path));
+  index.add_all(pathspecs, IndexAddOption::DEFAULT, Some(&mut keep_skipped))?;
+  index
+    .update_all(pathspecs, Some(&mut keep_skipped))
+    .map_err(GitError::from)
+}
+
+/// Makes the worktree match the sparse checkout of `dirs` (`None`: everything).
+/// Files leaving the cone are removed and flagged skip-worktree unless they have
+/// local changes; files entering it are checked out from the index.
+fn apply_sparse_checkout(repo: &Repository, dirs: Option<&[String]>) -> Result<(), GitError> {
+  let workdir = repo_workdir(repo)?.to_path_buf();
+  let mut index = repo.index()?;
+  if index.is_empty() {
+    // Worktrees created without a checkout have no index yet.
+    if let Some(commit) = head_commit(repo)? {
+      index.read_tree(&commit.tree()?)?;
+      index.write()?;
+    }
+  }
+  let mut changed = HashSet::new();
+  let mut options = git2::StatusOptions::new();
+  options.include_untracked(false);
+  for entry in repo.statuses(Some(&mut options))?.iter() {
+    if entry.status() != git2::Status::WT_DELETED {
+      if let Some(path) = entry.path() {
+        changed.insert(path.to_string());
+      }
+    }
+  }
+
+  let skip_flag = IndexEntryExtendedFlag::SKIP_WORKTREE.bits();
+  let mut missing = Vec::new();
+  let mut removed = Vec::new();
+  let entries: Vec<IndexEntry> = index.iter().collect();
+  for mut entry in entries {
+    // Conflicted entries and submodules stay as they are.
+    if (entry.flags >> 12) & 0x3 != 0 || entry.mode == 0o160000 {
+      continue;
+    }
+    let path = String::from_utf8_lossy(&entry.path).into_owned();
+    let file = workdir.join(&path);
+    let skipped = entry.flags_extended & skip_flag != 0;
+    let included = dirs.is_none_or(|dirs| in_sparse_cone(&path, dirs)) || changed.contains(&path);
+    if included {
+      if skipped {
+        entry.flags_extended &= !skip_flag;
+        index.add(&entry)?;
+      }
+      if std::fs::symlink_metadata(&file).is_err() {
+        missing.push(path);
+      }
+    } else {
+      if !skipped {
+        entry.flags_extended |= skip_flag;
+        index.add(&entry)?;
+      }
+      if std::fs::symlink_metadata(&file).is_ok() {
+        removed.push(file);
+      }
+    }
+  }
+  // The index is written before any file is touched, so a failed removal
+  // leaves a file behind rather than an index that disagrees with the disk.
+  index.write()?;
+  for file in removed {
+    std::fs::remove_file(&file)?;
+    // Drop directories left empty, stopping at the first non-empty one.
+    for dir in file.ancestors().skip(1).take_while(|dir| *dir != workdir) {
+      if std::fs::remove_dir(dir).is_err() {
+        break;
+      }
+    }
+  }
+
+  if !missing.is_empty() {
+    let mut checkout = CheckoutBuilder::new();
+    checkout.force().disable_pathspec_match(true);
+    for path in &missing {
+      checkout.path(path);
+    }
+    repo.checkout_index(Some(&mut index), Some(&mut checkout))?;
+  }
+  Ok(())
+}
+
+#[tauri::command]
+pub fn git_sparse_checkout_list(worktree_path: String) -> Result<GitSparseCheckout, GitError> {
+  let repo = open_repo(&worktree_path)?;
+  repo_workdir(&repo)?;
+  read_sparse_checkout(&repo)
+}
+
+/// Switches the worktree to a cone-mode sparse checkout of `paths` (directories,
+/// relative to the worktree root) and updates its files to match. Files at the
+/// root are always checked out. Files with local changes are kept.
+#[tauri::command]
+pub fn git_sparse_checkout_set(worktree_path: String, paths: Vec<String>) -> Result<(), GitError> {
+  let repo = open_repo(&worktree_path)?;
+  repo_workdir(&repo)?;
+  let dirs = normalize_sparse_dirs(&paths)?;
+  write_sparse_checkout(&repo, &dirs)?;
+  apply_sparse_checkout(&repo, Some(&dirs))
+}
+
+/// Adds `paths` to an existing cone-mode sparse checkout.
+#[tauri::command]
+pub fn git_sparse_checkout_add(worktree_path: String, paths: Vec<String>) -> Result<(), GitError> {
+  let repo = open_repo(&worktree_path)?;
+  repo_workdir(&repo)?;
+  let current = read_sparse_checkout(&repo)?;
+  if !current.cone {
+    return Err(GitError::new(
+      GitErrorCode::InvalidArgument,
+      "worktree is not a cone-mode sparse checkout",
+    ));
+  }
+  let dirs = normalize_sparse_dirs(&[current.paths, paths].concat())?;
+  write_sparse_checkout(&repo, &dirs)?;
+  apply_sparse_checkout(&repo, Some(&dirs))
+}
+
+/// Re-applies the sparse checkout, e.g. after a reset or merge, which libgit2
+/// performs without regard to it.
+#[tauri::command]
+pub fn git_sparse_checkout_reapply(worktree_path: String) -> Result<(), GitError> {
+  let repo = open_repo(&worktree_path)?;
+  repo_workdir(&repo)?;
+  let current = read_sparse_checkout(&repo)?;
+  if !current.cone {
+    return Err(GitError::new(
+      GitErrorCode::InvalidArgument,
+      "worktree is not a cone-mode sparse checkout",
+    ));
+  }
+  apply_sparse_checkout(&repo, Some(&current.paths))
+}
+
+/// Turns sparse checkout off and checks out every file again. The patterns are
+/// kept, as with `git sparse-checkout disable`.
+#[tauri::command]
+pub fn git_sparse_checkout_disable(worktree_path: String) -> Result<(), GitError> {
+  let repo = open_repo(&worktree_path)?;
+  repo_workdir(&repo)?;
+  if read_sparse_checkout(&repo)?.enabled {
+    git2::Config::open(&repo.path().join("config.worktree"))?
+      .set_bool("core.sparseCheckout", false)?;
+  }
+  apply_sparse_checkout(&repo, None)
+}
+
+/// Renames local branch `branch`, moving its reflog and upstream config along.
+/// Worktrees that have it checked out follow the rename.
+#[tauri::command]
+pub fn git_rename_branch(
+  repo_path: String,
+  branch: String,
+  new_name: String,
+  force: Option<bool>,
+) -> Result<(), GitError> {
+  validate_git_ref(&branch)?;
+  validate_git_ref(&new_name)?;
+  let repo = open_repo_at(&repo_path)?;
+  let mut local = repo.find_branch(&branch, BranchType::Local)?;
+  local.rename(&new_name, force.unwrap_or(false))?;
+  Ok(())
+}
+
+/// Points local branch `branch` at `target`, which may be any revision
+/// expression (e.g. `origin/main~2`). With `expected_oid` the move only
+/// happens if the branch still points there, failing with `RefChanged`
+/// otherwise. A branch checked out in some worktree is only moved with `force`,
+/// since that worktree's index and files would no longer match its HEAD.
+#[tauri::command]
+pub fn git_set_branch_target(
+  repo_path: String,
+  branch: String,
+  target: String,
+  expected_oid: Option<String>,
+  force: Option<bool>,
+) -> Result<String, GitError> {
+  validate_git_ref(&branch)?;
+  validate_revspec(&target)?;
+  if let Some(ref expected) = expected_oid {
+    validate_revspec(expected)?;
+  }
+  let repo = open_repo_at(&repo_path)?;
+  let ref_name = format!("refs/heads/{}", branch);
+  let current = repo
+    .find_reference(&ref_name)?
+    .target()
+    .ok_or_else(|| GitError::new(GitErrorCode::RefInvalid, "branch is a symbolic ref"))?;
+  if !force.unwrap_or(false) {
+    ensure_not_checked_out(&repo, &ref_name)?;
+  }
+  let new_oid = resolve_commit(&repo, &target)?.id();
+  let log_message = format!("branch: set to {}", target);
+  match expected_oid {
+    Some(expected) => {
+      let expected = Oid::from_str(&expected)
+        .map_err(|_| GitError::new(GitErrorCode::InvalidArgument, "invalid expected oid"))?;
+      if expected != current {
+        return Err(
+          GitError::new(GitErrorCode::RefChanged, "branch moved since it was read")
+            .with_details(serde_json::json!({
+              "expected": expected.to_string(),
+              "actual": current.to_string(),
+            })),
+        );
+      }
+      // Re-checked under the ref lock, in case it moved after the read above.
+      repo.reference_matching(&ref_name, new_oid, true, expected, &log_message)?;
+    }
+    None => {
+      repo.reference(&ref_name, new_oid, true, &log_message)?;
+    }
+  }
+  Ok(new_oid.to_string())
+}
+
+/// Sets the upstream of local branch `branch` to `upstream` (e.g.
+/// `origin/main`), or clears it when `None`.
+#[tauri::command]
+pub fn git_set_upstream(
+  repo_path: String,
+  branch: String,
+  upstream: Option<String>,
+) -> Result<(), GitError> {
+  validate_git_ref(&branch)?;
+  if let Some(ref value) = upstream {
+    validate_git_ref(value)?;
+  }
+  let repo = open_repo_at(&repo_path)?;
+  let mut local = repo.find_branch(&branch, BranchType::Local)?;
+  local.set_upstream(upstream.as_deref())?;
+  Ok(())
+}
+
+#[tauri::command]
+pub fn git_delete_branch(
+  repo_path: String,
+  branch: String,
+  force: Option<bool>,
+) -> Result<(), GitError> {
+  validate_git_ref(&branch)?;
+  let repo = open_repo_at(&repo_path)?;
+  let mut reference = repo.find_reference(&format!("refs/heads/{}", branch))?;
+
+  if force.unwrap_or(false) {
+    reference.delete()?;
+    return Ok(());
+  }
+
+  let head = repo.head()?;
+  if head.name() == reference.name() {
+    return Err(GitError::new(
+      GitErrorCode::BranchCheckedOut,
+      "cannot delete checked out branch",
+    ));
+  }
+
+  let head_commit = head
+    .peel(ObjectType::Commit)?
+    .into_commit()
+    .map_err(|_| invalid_commit())?;
+  let branch_commit = reference
+    .peel(ObjectType::Commit)?
+    .into_commit()
+    .map_err(|_| invalid_commit())?;
+  let (ahead, _) = repo
+    .graph_ahead_behind(branch_commit.id(), head_commit.id())?;
+  if ahead > 0 {
+    return Err(
+      GitError::new(GitErrorCode::BranchNotMerged, "branch is not fully merged")
+        .with_details(serde_json::json!({ "ahead": ahead })),
+    );
+  }
+
+  reference
+    .delete()
+    .map_err(GitError::from)
+}
+
+fn compare_commits(
+  repo: &Repository,
+  base: &str,
+  base_oid: Oid,
+  head: &str,
+) -> Result<GitRefComparison, GitError> {
+  validate_revspec(head)?;
+  let head_oid = resolve_commit(repo, head)?.id();
+  let (ahead, behind) = repo.graph_ahead_behind(head_oid, base_oid)?;
+  let merge_base = match repo.merge_base(base_oid, head_oid) {
+    Ok(oid) => Some(oid),
+    Err(err) if err.code() == ErrorCode::NotFound => None,
+    Err(err) => return Err(err.into()),
+  };
+  Ok(GitRefComparison {
+    base: base.to_string(),
+    head: head.to_string(),
+    base_oid: base_oid.to_string(),
+    head_oid: Some(head_oid.to_string()),
+    ahead,
+    behind,
+    merge_base: merge_base.map(|oid| oid.to_string()),
+    can_fast_forward: merge_base == Some(base_oid),
+    error: None,
+  })
+}
+
+#[tauri::command]
+pub fn git_compare_refs(
+  repo_path: String,
+  base: String,
+  head: String,
+) -> Result<GitRefComparison, GitError> {
+  validate_revspec(&base)?;
+  let repo = open_repo_at(&repo_path)?;
+  let base_oid = resolve_commit(&repo, &base)?.id();
+  compare_commits(&repo, &base, base_oid, &head)
+}
+
+/// Compares each of `heads` against the same `base`, e.g. every attempt branch
+/// against the workspace branch, in a single call. A head that can't be
+/// compared gets its `error` set instead of failing the whole batch.
+#[tauri::command]
+pub fn git_compare_refs_batch(
+  repo_path: String,
+  base: String,
+  heads: Vec<String>,
+) -> Result<Vec<GitRefComparison>, GitError> {
+  validate_revspec(&base)?;
+  let repo = open_repo_at(&repo_path)?;
+  let base_oid = resolve_commit(&repo, &base)?.id();
+  let compare = |head: &String| {
+    compare_commits(&repo, &base, base_oid, head).unwrap_or_else(|err| GitRefComparison {
+      base: base.clone(),
+      head: head.clone(),
+      base_oid: base_oid.to_string(),
+      head_oid: None,
+      ahead: 0,
+      behind: 0,
+      merge_base: None,
+      can_fast_forward: false,
+      error: Some(err),
+    })
+  };
+  Ok(heads.iter().map(compare).collect())
+}
+
+#[tauri::command]
+pub fn git_current_commit(repo_path: String) -> Result<String, GitError> {
+  with_cached_repo(&repo_path, |repo| {
+    let head = repo.head()?;
+    let target = head
+      .target()
+      .ok_or_else(|| GitError::new(GitErrorCode::RefNotFound, "HEAD is unborn"))?;
+    Ok(target.to_string())
+  })
+}
+
+#[tauri::command]
+pub fn git_reset_hard(repo_path: String, git_ref: String) -> Result<(), GitError> {
+  validate_git_ref(&git_ref)?;
+  let repo = open_repo_at(&repo_path)?;
+  let object = repo.revparse_single(&git_ref)?;
+  repo
+    .reset(&object, ResetType::Hard, None)
+    .map(|_| ())
+    .map_err(GitError::from)
+}
+
+fn collect_status(
+  repo: &Repository,
+  include_ignored: bool,
+) -> Result<Vec<GitStatusEntry>, GitError> {
+  let mut options = git2::StatusOptions::new();
+  options
+    .include_untracked(true)
+    .recurse_untracked_dirs(true)
+    .include_ignored(include_ignored)
+    .renames_head_to_index(true)
+    .renames_index_to_workdir(true);
+  let statuses = repo.statuses(Some(&mut options))?;
+  let skipped = skip_worktree_paths(&repo.index()?);
+  let mut entries = Vec::new();
+  for entry in statuses.iter() {
+    let status = entry.status();
+    if is_sparse_deletion(status, entry.path(), &skipped) {
+      continue;
+    }
+    // Renames are reported on the entry of the new path, with the old path
+    // only available from the delta.
+    let staged = entry.head_to_index();
+    let unstaged = entry.index_to_workdir();
+    let path = unstaged
+      .as_ref()
+      .and_then(|delta| delta.new_file().path())
+      .or_else(|| staged.as_ref().and_then(|delta| delta.new_file().path()))
+      .map(|value| value.to_string_lossy().into_owned())
+      .or_else(|| entry.path().map(|value| value.to_string()))
+      .unwrap_or_default();
+    if path.is_empty() {
+      continue;
+    }
+    let orig_path = [staged, unstaged]
+      .into_iter()
+      .flatten()
+      .filter(|delta| delta.status() == Delta::Renamed)
+      .find_map(|delta| delta.old_file().path().map(Path::to_path_buf))
+      .map(|value| value.to_string_lossy().into_owned());
+    if let Some(kind) = status_to_kind(status) {
+      entries.push(GitStatusEntry {
+        path,
+        status: kind,
+        index_status: index_status_kind(status),
+        worktree_status: worktree_status_kind(status),
+        orig_path,
+      });
+    }
+  }
+  Ok(entries)
+}
+
+/// Resolves the upstream of local branch `name` and counts commits of `local`
+/// not in it (ahead) and in it but not in `local` (behind).
+fn upstream_tracking(
+  repo: &Repository,
+  name: &str,
+  local: Option<Oid>,
+) -> Result<(Option<String>, usize, usize), GitError> {
+  let upstream = match repo
+    .find_branch(name, BranchType::Local)
+    .and_then(|branch| branch.upstream())
+  {
+    Ok(value) => value,
+    Err(_) => return Ok((None, 0, 0)),
+  };
+  let upstream_name = upstream.name()?.map(|value| value.to_string());
+  let (ahead, behind) = match (local, upstream.get().target()) {
+    (Some(local), Some(remote)) => repo.graph_ahead_behind(local, remote)?,
+    _ => (0, 0),
+  };
+  Ok((upstream_name, ahead, behind))
+}
+
+fn branch_header(repo: &Repository) -> Result<GitBranchHeader, GitError> {
+  let detached = repo.head_detached().unwrap_or(false);
+  let head = head_commit(repo)?.map(|commit| commit.id());
+  // Read HEAD's symbolic target directly so unborn branches still report a name.
+  let branch = if detached {
+    None
+  } else {
+    repo
+      .find_reference("HEAD")?
+      .symbolic_target()
+      .and_then(|target| target.strip_prefix("refs/heads/"))
+      .map(|value| value.to_string())
+  };
+  let (upstream, ahead, behind) = match branch.as_deref() {
+    Some(name) => upstream_tracking(repo, name, head)?,
+    None => (None, 0, 0),
+  };
+  Ok(GitBranchHeader {
+    branch,
+    head: head.map(|oid| oid.to_string()),
+    detached,
+    upstream,
+    ahead,
+    behind,
+  })
+}
+
+/// Lists changed paths with separate index and worktree states. Renames are
+/// detected in both; ignored files are only listed with `include_ignored`.
+#[tauri::command]
+pub fn git_status(
+  repo_path: String,
+  include_ignored: Option<bool>,
+) -> Result<Vec<GitStatusEntry>, GitError> {
+  let repo = open_repo_at(&repo_path)?;
+  collect_status(&repo, include_ignored.unwrap_or(false))
+}
+
+/// `git_status` plus the branch header: current branch, upstream and how far
+/// HEAD is ahead of and behind it.
+#[tauri::command]
+pub fn git_status_summary(
+  repo_path: String,
+  include_ignored: Option<bool>,
+) -> Result<GitStatusSummary, GitError> {
+  let repo = open_repo_at(&repo_path)?;
+  Ok(GitStatusSummary {
+    branch: branch_header(&repo)?,
+    entries: collect_status(&repo, include_ignored.unwrap_or(false))?,
+  })
+}
+
+/// Lists changed paths; both sides of a rename are included.
+#[tauri::command]
+pub fn git_changed_files(repo_path: String) -> Result<Vec<String>, GitError> {
+  let entries = git_status(repo_path, None)?;
+  Ok(
+    entries
+      .into_iter()
+      .flat_map(|entry| entry.orig_path.into_iter().chain([entry.path]))
+      .collect(),
+  )
+}
+
+/// Stages `paths` like `git add -A -- <paths>`: new and modified files are added
+/// and deleted files are removed from the index. An empty list stages everything.
+#[tauri::command]
+pub fn git_stage(repo_path: String, paths: Vec<String>) -> Result<(), GitError> {
+  let repo = open_repo_at(&repo_path)?;
+  repo_workdir(&repo)?;
+  let mut index = repo.index()?;
+  add_all_to_index(&mut index, &paths)?;
+  index.write().map_err(GitError::from)
+}
+
+/// Resets the index entries for `paths` back to HEAD, leaving the worktree untouched.
+/// An empty list unstages everything.
+#[tauri::command]
+pub fn git_unstage(repo_path: String, paths: Vec<String>) -> Result<(), GitError> {
+  let repo = open_repo_at(&repo_path)?;
+  // On an unborn branch there is no tree to reset to, so the entries are removed.
+  let head = head_commit(&repo)?;
+  repo
+    .reset_default(head.as_ref().map(|commit| commit.as_object()), paths.iter())
+    .map_err(GitError::from)
+}
+
+/// Writes the current index as a commit on HEAD and returns its oid.
+#[tauri::command]
+pub fn git_commit(
+  repo_path: String,
+  message: String,
+  author: Option<GitSignature>,
+  committer: Option<GitSignature>,
+  allow_empty: Option<bool>,
+  amend: Option<bool>,
+) -> Result<String, GitError> {
+  if message.trim().is_empty() {
+    return Err(GitError::new(GitErrorCode::InvalidArgument, "commit message is empty"));
+  }
+  let repo = open_repo_at(&repo_path)?;
+  let mut index = repo.index()?;
+  if index.has_conflicts() {
+    return Err(GitError::new(GitErrorCode::Conflict, "index has unresolved conflicts"));
+  }
+  let tree_oid = index.write_tree()?;
+  let tree = repo.find_tree(tree_oid)?;
+
+  let author = match author {
+    Some(value) => to_signature(&value)?,
+    None => signature_or_default(&repo)?,
+  };
+  // Without an explicit committer, fall back to the configured identity and
+  // then to the author so an override alone is enough on unconfigured machines.
+  let committer = match committer {
+    Some(value) => to_signature(&value)?,
+    None => repo.signature().unwrap_or_else(|_| author.to_owned()),
+  };
+
+  let head = head_commit(&repo)?;
+
+  if amend.unwrap_or(false) {
+    let head = head.ok_or_else(|| {
+      GitError::new(GitErrorCode::RefNotFound, "HEAD is unborn; nothing to amend")
+    })?;
+    return head
+      .amend(
+        Some("HEAD"),
+        Some(&author),
+        Some(&committer),
+        None,
+        Some(&message),
+        Some(&tree),
+      )
+      .map(|oid| oid.to_string())
+      .map_err(GitError::from);
+  }
+
+  if !allow_empty.unwrap_or(false) {
+    let unchanged = match &head {
+      Some(commit) => commit.tree_id() == tree_oid,
+      None => tree.is_empty(),
+    };
+    if unchanged {
+      return Err(GitError::new(GitErrorCode::NothingToCommit, "nothing to commit"));
+    }
+  }
+
+  let parents: Vec<&git2::Commit> = head.iter().collect();
+  repo
+    .commit(Some("HEAD"), &author, &committer, &message, &tree, &parents)
+    .map(|oid| oid.to_string())
+    .map_err(GitError::from)
+}
 
-  if create_branch {
-    create_branch_at_head(&repo, &branch)?;
+#[tauri::command]
+pub fn git_diff(
+  repo_path: String,
+  mode: GitDiffMode,
+  from: Option<String>,
+  to: Option<String>,
+  paths: Option<Vec<String>>,
+  context_lines: Option<u32>,
+) -> Result<Vec<GitDiffFile>, GitError> {
+  if let Some(ref spec) = from {
+    validate_revspec(spec)?;
+  }
+  if let Some(ref spec) = to {
+    validate_revspec(spec)?;
   }
+  let repo = open_repo_at(&repo_path)?;
 
-  let reference = repo
-    .find_reference(&format!("refs/heads/{}", branch))
-    .map_err(|err| err.to_string())?;
+  let mut opts = DiffOptions::new();
+  opts.context_lines(context_lines.unwrap_or(3).min(200));
+  for path in paths.iter().flatten() {
+    opts.pathspec(path);
+  }
+  if matches!(mode, GitDiffMode::IndexToWorkdir | GitDiffMode::HeadToWorkdir) {
+    opts
+      .include_untracked(true)
+      .recurse_untracked_dirs(true)
+      .show_untracked_content(true);
+  }
 
-  let mut opts = WorktreeAddOptions::new();
-  opts.reference(Some(&reference));
+  let mut diff = match mode {
+    GitDiffMode::TreeToTree => {
+      let old_tree = resolve_tree(&repo, required_rev(from.as_deref(), "from")?)?;
+      let new_tree = resolve_tree(&repo, required_rev(to.as_deref(), "to")?)?;
+      repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(&mut opts))
+    }
+    GitDiffMode::TreeToIndex => {
+      let old_tree = resolve_tree_or_unborn(&repo, from.as_deref())?;
+      repo.diff_tree_to_index(old_tree.as_ref(), None, Some(&mut opts))
+    }
+    GitDiffMode::IndexToWorkdir => repo.diff_index_to_workdir(None, Some(&mut opts)),
+    GitDiffMode::HeadToWorkdir => {
+      let old_tree = resolve_tree_or_unborn(&repo, from.as_deref())?;
+      repo.diff_tree_to_workdir_with_index(old_tree.as_ref(), Some(&mut opts))
+    }
+    GitDiffMode::BranchToBranch => {
+      let base = resolve_commit(&repo, required_rev(from.as_deref(), "from")?)?;
+      let head = resolve_commit(&repo, required_rev(to.as_deref(), "to")?)?;
+      let merge_base = repo.merge_base(base.id(), head.id())?;
+      let old_tree = repo
+        .find_commit(merge_base)
+        .and_then(|commit| commit.tree())?;
+      let new_tree = head.tree()?;
+      repo.diff_tree_to_tree(Some(&old_tree), Some(&new_tree), Some(&mut opts))
+    }
+  }?;
 
-  let name = worktree_name_from_path(&path_buf, &branch);
-  repo
-    .worktree(&name, &path_buf, Some(&mut opts))
-    .map(|_| ())
-    .map_err(|err| err.to_string())
+  diff.find_similar(None)?;
+  let mut files = diff_to_files(&diff)?;
+  if matches!(mode, GitDiffMode::IndexToWorkdir | GitDiffMode::HeadToWorkdir) {
+    // Files outside a sparse checkout are missing on purpose, not deleted.
+    let skipped = skip_worktree_paths(&repo.index()?);
+    files.retain(|file| {
+      file.status != "deleted"
+        || !file.old_path.as_ref().is_some_and(|path| skipped.contains(Path::new(path)))
+    });
+  }
+  Ok(files)
 }
 
-#[tauri::command]
-pub fn git_remove_worktree(
-  worktree_path: String,
-  force: Option<bool>,
-) -> Result<(), String> {
-  let path = PathBuf::from(&worktree_path);
-  let repo = open_repo(&worktree_path)?;
-  let worktree = Worktree::open_from_repository(&repo).map_err(|err| err.to_string())?;
+// Commits `git_log` returns without a limit, and the most it returns at all.
+const DEFAULT_LOG_LIMIT: usize = 100;
+const MAX_LOG_LIMIT: usize = 1000;
 
-  if !force.unwrap_or(false) {
-    ensure_clean_worktree(&path)?;
+/// Walks history newest first, like `git log`. `rev` is a single revision
+/// (default HEAD), a `base..head` range or a `base...head` symmetric difference;
+/// an empty side of a range means HEAD.
+#[tauri::command]
+pub fn git_log(
+  repo_path: String,
+  rev: Option<String>,
+  paths: Option<Vec<String>>,
+  author: Option<String>,
+  limit: Option<usize>,
+  offset: Option<usize>,
+  first_parent: Option<bool>,
+) -> Result<Vec<GitCommitInfo>, GitError> {
+  // Checked for `...` first, which would otherwise split as `..` plus a `.`.
+  let (base, head, symmetric) = match rev.as_deref() {
+    Some(spec) => match spec.split_once("...") {
+      Some((base, head)) => (Some(non_empty(base).unwrap_or("HEAD")), non_empty(head), true),
+      None => match spec.split_once("..") {
+        Some((base, head)) => (Some(non_empty(base).unwrap_or("HEAD")), non_empty(head), false),
+        None => (None, Some(spec), false),
+      },
+    },
+    None => (None, None, false),
+  };
+  for spec in [base, head].into_iter().flatten() {
+    validate_revspec(spec)?;
   }
+  let repo = open_repo_at(&repo_path)?;
+  let first_parent = first_parent.unwrap_or(false);
 
-  let mut prune_opts = WorktreePruneOptions::new();
-  prune_opts.valid(true);
-  prune_opts.working_tree(true);
-  if force.unwrap_or(false) {
-    prune_opts.locked(true);
+  let mut walk = repo.revwalk()?;
+  walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+  if first_parent {
+    walk.simplify_first_parent()?;
+  }
+  let head = match head {
+    Some(spec) => resolve_commit(&repo, spec)?.id(),
+    None => match head_commit(&repo)? {
+      Some(commit) => commit.id(),
+      None => return Ok(Vec::new()),
+    },
+  };
+  walk.push(head)?;
+  if let Some(spec) = base {
+    let base = resolve_commit(&repo, spec)?.id();
+    if symmetric {
+      // Commits on either side but not on both.
+      walk.push(base)?;
+      match repo.merge_bases(base, head) {
+        Ok(bases) => {
+          for oid in bases.iter() {
+            walk.hide(*oid)?;
+          }
+        }
+        Err(err) if err.code() == ErrorCode::NotFound => {}
+        Err(err) => return Err(err.into()),
+      }
+    } else {
+      walk.hide(base)?;
+    }
   }
 
-  worktree
-    .prune(Some(&mut prune_opts))
-    .map(|_| ())
-    .map_err(|err| err.to_string())
+  let mut path_opts = match paths {
+    Some(paths) if !paths.is_empty() => {
+      let mut opts = DiffOptions::new();
+      for path in &paths {
+        opts.pathspec(path);
+      }
+      Some(opts)
+    }
+    _ => None,
+  };
+  let author = author
+    .filter(|value| !value.is_empty())
+    .map(|value| value.to_lowercase());
+  let limit = limit.unwrap_or(DEFAULT_LOG_LIMIT).min(MAX_LOG_LIMIT);
+  let mut skip = offset.unwrap_or(0);
+
+  let mut commits = Vec::new();
+  for oid in walk {
+    if commits.len() >= limit {
+      break;
+    }
+    let commit = repo.find_commit(oid?)?;
+    if let Some(ref needle) = author {
+      let signature = commit.author();
+      let matches = [signature.name_bytes(), signature.email_bytes()]
+        .iter()
+        .any(|value| String::from_utf8_lossy(value).to_lowercase().contains(needle));
+      if !matches {
+        continue;
+      }
+    }
+    if let Some(ref mut opts) = path_opts {
+      if !commit_touches_paths(&repo, &commit, opts, first_parent)? {
+        continue;
+      }
+    }
+    if skip > 0 {
+      skip -= 1;
+      continue;
+    }
+    commits.push(commit_info(&commit));
+  }
+  Ok(commits)
 }
 
+/// Merges `branch` into HEAD. Conflicts, whether between the branches or with
+/// uncommitted edits in the worktree, are reported instead of applied, so the
+/// worktree is only touched when the whole merge can go through. With `dry_run`
+/// nothing is written either way.
 #[tauri::command]
-pub fn git_delete_branch(
+pub fn git_merge(
   repo_path: String,
   branch: String,
-  force: Option<bool>,
-) -> Result<(), String> {
-  validate_git_ref(&branch)?;
+  mode: GitMergeMode,
+  message: Option<String>,
+  dry_run: Option<bool>,
+) -> Result<GitMergeResult, GitError> {
+  validate_revspec(&branch)?;
   let repo = open_repo_at(&repo_path)?;
-  let mut reference = repo
-    .find_reference(&format!("refs/heads/{}", branch))
-    .map_err(|err| err.to_string())?;
+  let dry_run = dry_run.unwrap_or(false);
+  let theirs = resolve_commit(&repo, &branch)?;
+  let ours = head_commit(&repo)?;
 
-  if force.unwrap_or(false) {
-    reference
-      .delete()
-      .map_err(|err| err.to_string())?;
-    return Ok(());
+  ensure_index_matches_head(&repo)?;
+  let head_tree = ours.as_ref().map(|commit| commit.tree()).transpose()?;
+
+  let annotated = repo.find_annotated_commit(theirs.id())?;
+  let (analysis, _) = repo.merge_analysis(&[&annotated])?;
+  let result = |status, oid: Option<Oid>, conflicts| GitMergeResult {
+    status,
+    oid: oid.map(|value| value.to_string()),
+    conflicts,
+  };
+  if analysis.is_up_to_date() {
+    return Ok(result(GitMergeStatus::UpToDate, None, Vec::new()));
   }
 
-  let head = repo.head().map_err(|err| err.to_string())?;
-  if head.name() == reference.name() {
-    return Err("cannot delete checked out branch".to_string());
+  let fast_forward = analysis.is_fast_forward() || analysis.is_unborn();
+  if fast_forward && mode != GitMergeMode::Squash {
+    let tree = theirs.tree()?;
+    let conflicts = checkout_conflicts(&repo, &tree)?;
+    if !conflicts.is_empty() {
+      return Ok(result(GitMergeStatus::Conflicts, None, conflicts));
+    }
+    if dry_run {
+      return Ok(result(GitMergeStatus::FastForward, None, Vec::new()));
+    }
+    checkout_tree_safe(&repo, &tree)?;
+    move_head(&repo, theirs.id(), &format!("merge {}: Fast-forward", branch))?;
+    return Ok(result(GitMergeStatus::FastForward, Some(theirs.id()), Vec::new()));
+  }
+  if mode == GitMergeMode::FastForwardOnly {
+    return Err(GitError::new(
+      GitErrorCode::NotFastForward,
+      format!("cannot fast-forward to {}", branch),
+    ));
   }
 
-  let head_commit = head
-    .peel(ObjectType::Commit)
-    .map_err(|err| err.to_string())?
-    .into_commit()
-    .map_err(|_| "invalid commit".to_string())?;
-  let branch_commit = reference
-    .peel(ObjectType::Commit)
-    .map_err(|err| err.to_string())?
-    .into_commit()
-    .map_err(|_| "invalid commit".to_string())?;
-  let (ahead, _) = repo
-    .graph_ahead_behind(branch_commit.id(), head_commit.id())
-    .map_err(|err| err.to_string())?;
-  if ahead > 0 {
-    return Err("branch is not fully merged".to_string());
+  let ancestor_tree = match &ours {
+    Some(ours) => {
+      let base = repo.merge_base(ours.id(), theirs.id())?;
+      repo.find_commit(base)?.tree()?
+    }
+    None => repo.find_tree(repo.treebuilder(None)?.write()?)?,
+  };
+  let our_tree = head_tree.clone().unwrap_or_else(|| ancestor_tree.clone());
+  let mut merged = repo.merge_trees(&ancestor_tree, &our_tree, &theirs.tree()?, None)?;
+  if merged.has_conflicts() {
+    return Ok(result(GitMergeStatus::Conflicts, None, conflict_paths(&merged)?));
+  }
+  // Checked against the in-memory index so a dry run writes no objects.
+  let diff = repo.diff_tree_to_index(Some(&our_tree), Some(&merged), None)?;
+  let conflicts = dirty_paths_in_diff(&repo, &diff)?;
+  if !conflicts.is_empty() {
+    return Ok(result(GitMergeStatus::Conflicts, None, conflicts));
+  }
+  let status = match mode {
+    GitMergeMode::Squash => GitMergeStatus::Squashed,
+    _ => GitMergeStatus::Merged,
+  };
+  if dry_run {
+    return Ok(result(status, None, Vec::new()));
   }
 
-  reference
-    .delete()
-    .map_err(|err| err.to_string())
+  let message = match message.filter(|value| !value.trim().is_empty()) {
+    Some(value) => value,
+    None if status == GitMergeStatus::Squashed => format!("Squashed commit of '{}'", branch),
+    None => format!("Merge branch '{}'", branch),
+  };
+  let signature = signature_or_default(&repo)?;
+  let mut parents: Vec<&git2::Commit> = ours.iter().collect();
+  if status == GitMergeStatus::Merged {
+    parents.push(&theirs);
+  }
+  let tree = repo.find_tree(merged.write_tree_to(&repo)?)?;
+  checkout_tree_safe(&repo, &tree)?;
+  let oid = repo.commit(Some("HEAD"), &signature, &signature, &message, &tree, &parents)?;
+  Ok(result(status, Some(oid), Vec::new()))
 }
 
+/// Applies `commits` on top of HEAD in order, keeping their authors and messages.
+/// Each commit is checked in memory first; the first one that conflicts stops
+/// the sequence. Its conflicts are checked out into the index and worktree and
+/// the pick is recorded like `git cherry-pick` does, so once they're resolved
+/// with `git_conflicts`/`git_resolve_conflict` the sequence can be continued,
+/// skipped past or aborted in a later call. A commit that would overwrite local
+/// changes is reported without touching anything or recording state; it and
+/// `remaining` have to be picked again once the changes are dealt with.
 #[tauri::command]
-pub fn git_current_commit(repo_path: String) -> Result<String, String> {
-  with_cached_repo(&repo_path, |repo| {
-    let head = repo.head().map_err(|err| err.to_string())?;
-    let target = head.target().ok_or_else(|| "HEAD is unborn".to_string())?;
-    Ok(target.to_string())
-  })
+pub fn git_cherry_pick(
+  repo_path: String,
+  commits: Vec<String>,
+  mainline: Option<u32>,
+  action: Option<GitCherryPickAction>,
+) -> Result<GitCherryPickResult, GitError> {
+  for spec in &commits {
+    validate_revspec(spec)?;
+  }
+  let repo = open_repo_at(&repo_path)?;
+  let mut result = GitCherryPickResult {
+    status: GitCherryPickStatus::Picked,
+    picked: Vec::new(),
+    skipped: Vec::new(),
+    failed: None,
+    conflicts: Vec::new(),
+    remaining: Vec::new(),
+  };
+  let state = read_cherry_pick_state(&repo)?;
+  let stopped = resolve_commit(&repo, "CHERRY_PICK_HEAD").ok();
+  let head = head_commit(&repo)?
+    .ok_or_else(|| GitError::new(GitErrorCode::RefNotFound, "HEAD is unborn"))?;
+  let orig_head = match &state {
+    Some(state) => Oid::from_str(&state.orig_head)?,
+    None => head.id(),
+  };
+  match action.unwrap_or_default() {
+    GitCherryPickAction::Start => {
+      if state.is_some() || stopped.is_some() {
+        return Err(GitError::new(
+          GitErrorCode::InvalidArgument,
+          "a cherry-pick is in progress; continue, skip or abort it first",
+        ));
+      }
+      ensure_index_matches_head(&repo)?;
+      let commits = commits
+        .iter()
+        .map(|spec| resolve_commit(&repo, spec).map(|commit| commit.id()))
+        .collect::<Result<Vec<_>, _>>()?;
+      run_cherry_pick(&repo, &commits, mainline, head.id(), &mut result)?;
+      return Ok(result);
+    }
+    _ if state.is_none() && stopped.is_none() => {
+      return Err(GitError::new(GitErrorCode::InvalidArgument, "no cherry-pick in progress"));
+    }
+    GitCherryPickAction::Abort => {
+      let target = repo.find_commit(orig_head)?;
+      repo.reset(target.as_object(), ResetType::Hard, None)?;
+      clear_cherry_pick_state(&repo)?;
+      result.status = GitCherryPickStatus::Aborted;
+      return Ok(result);
+    }
+    GitCherryPickAction::Skip => {
+      // Drop the stopped commit's partial changes before moving on.
+      repo.reset(head.as_object(), ResetType::Hard, None)?;
+    }
+    GitCherryPickAction::Continue => {
+      let mut index = repo.index()?;
+      if index.has_conflicts() {
+        return Err(GitError::new(GitErrorCode::Conflict, "index has unresolved conflicts"));
+      }
+      // Without CHERRY_PICK_HEAD the stopped commit was already committed,
+      // e.g. with `git cherry-pick --continue`.
+      if let Some(stopped) = stopped {
+        let tree = repo.find_tree(index.write_tree()?)?;
+        if tree.id() == head.tree_id() {
+          result.skipped.push(stopped.id().to_string());
+        } else {
+          let message = std::fs::read_to_string(repo.path().join("MERGE_MSG"))
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .unwrap_or_else(|| String::from_utf8_lossy(stopped.message_bytes()).into_owned());
+          let oid = repo.commit(
+            Some("HEAD"),
+            &stopped.author(),
+            &signature_or_default(&repo)?,
+            &message,
+            &tree,
+            &[&head],
+          )?;
+          result.picked.push(oid.to_string());
+        }
+      }
+    }
+  }
+  clear_cherry_pick_state(&repo)?;
+  let (mainline, remaining) = match state {
+    Some(state) => (state.mainline, state.remaining),
+    None => (None, Vec::new()),
+  };
+  let remaining = remaining
+    .iter()
+    .map(|oid| Oid::from_str(oid))
+    .collect::<Result<Vec<_>, _>>()?;
+  run_cherry_pick(&repo, &remaining, mainline, orig_head, &mut result)?;
+  Ok(result)
 }
 
+/// Rebases `branch` (default HEAD) onto `onto` (default `upstream`), like
+/// `git rebase [--onto <onto>] <upstream> [<branch>]`. Progress is kept in the
+/// repository's rebase state, so a rebase stopped on conflicts can be resolved
+/// and then continued, skipped or aborted in a later call.
 #[tauri::command]
-pub fn git_reset_hard(repo_path: String, git_ref: String) -> Result<(), String> {
-  validate_git_ref(&git_ref)?;
+pub fn git_rebase(
+  repo_path: String,
+  action: GitRebaseAction,
+  upstream: Option<String>,
+  onto: Option<String>,
+  branch: Option<String>,
+) -> Result<GitRebaseResult, GitError> {
+  for spec in [&upstream, &onto].into_iter().flatten() {
+    validate_revspec(spec)?;
+  }
+  if let Some(ref name) = branch {
+    validate_git_ref(name)?;
+  }
   let repo = open_repo_at(&repo_path)?;
-  let object = repo
-    .revparse_single(&git_ref)
-    .map_err(|err| err.to_string())?;
-  repo
-    .reset(&object, ResetType::Hard, None)
-    .map(|_| ())
-    .map_err(|err| err.to_string())
+  let mut options = RebaseOptions::new();
+
+  let mut rebase = match action {
+    GitRebaseAction::Start => {
+      let upstream = upstream.ok_or_else(|| {
+        GitError::new(GitErrorCode::InvalidArgument, "rebase start requires `upstream`")
+      })?;
+      let annotated = |spec: &str| -> Result<git2::AnnotatedCommit, GitError> {
+        let commit = resolve_commit(&repo, spec)?;
+        repo.find_annotated_commit(commit.id()).map_err(GitError::from)
+      };
+      let upstream = annotated(&upstream)?;
+      let onto = onto.as_deref().map(annotated).transpose()?;
+      // Passing the branch by reference name keeps it checked out after the rebase.
+      let branch = match branch {
+        Some(name) => {
+          let reference = repo.find_branch(&name, git2::BranchType::Local)?.into_reference();
+          Some(repo.reference_to_annotated_commit(&reference)?)
+        }
+        None => None,
+      };
+      repo.rebase(branch.as_ref(), Some(&upstream), onto.as_ref(), Some(&mut options))?
+    }
+    GitRebaseAction::Continue => {
+      let mut rebase = repo.open_rebase(Some(&mut options))?;
+      if repo.index()?.has_conflicts() {
+        return Err(GitError::new(GitErrorCode::Conflict, "index has unresolved conflicts"));
+      }
+      if rebase.operation_current().is_some() {
+        commit_rebase_operation(&mut rebase, &signature_or_default(&repo)?)?;
+      }
+      rebase
+    }
+    GitRebaseAction::Skip => {
+      let rebase = repo.open_rebase(Some(&mut options))?;
+      // Drop the stopped operation's partial changes before moving on.
+      let head = repo.head()?.peel(ObjectType::Commit)?;
+      repo.reset(&head, ResetType::Hard, None)?;
+      rebase
+    }
+    GitRebaseAction::Abort => {
+      let mut rebase = repo.open_rebase(Some(&mut options))?;
+      let total = rebase.len();
+      rebase.abort()?;
+      return Ok(GitRebaseResult {
+        status: GitRebaseStatus::Aborted,
+        head: Some(head_oid_string(&repo)),
+        current: None,
+        step: 0,
+        total,
+        conflicts: Vec::new(),
+      });
+    }
+  };
+  // Resolved only now so aborting works without an identity.
+  run_rebase(&repo, &mut rebase, &signature_or_default(&repo)?)
 }
 
+/// Lists conflicted index entries with their ancestor/ours/theirs stages.
+/// With `merged`, each text conflict also carries the file merged with markers.
 #[tauri::command]
-pub fn git_status(repo_path: String) -> Result<Vec<GitStatusEntry>, String> {
+pub fn git_conflicts(
+  repo_path: String,
+  paths: Option<Vec<String>>,
+  merged: Option<bool>,
+) -> Result<Vec<GitConflictEntry>, GitError> {
   let repo = open_repo_at(&repo_path)?;
-  let mut options = git2::StatusOptions::new();
-  options
-    .include_untracked(true)
-    .recurse_untracked_dirs(true)
-    .include_ignored(false);
-  let statuses = repo.statuses(Some(&mut options)).map_err(|err| err.to_string())?;
+  let index = repo.index()?;
   let mut entries = Vec::new();
-  for entry in statuses.iter() {
-    let status = entry.status();
-    let path = entry
-      .path()
-      .map(|value| value.to_string())
-      .unwrap_or_default();
-    if path.is_empty() {
-      continue;
+  for conflict in index.conflicts()? {
+    let conflict = conflict?;
+    let path = match conflict
+      .our
+      .as_ref()
+      .or(conflict.their.as_ref())
+      .or(conflict.ancestor.as_ref())
+    {
+      Some(entry) => String::from_utf8_lossy(&entry.path).to_string(),
+      None => continue,
+    };
+    if let Some(ref filter) = paths {
+      if !filter.contains(&path) {
+        continue;
+      }
     }
-    if let Some(kind) = status_to_kind(status) {
-      entries.push(GitStatusEntry { path, status: kind });
+    let side = |entry: &Option<IndexEntry>| {
+      entry.as_ref().map(|entry| conflict_side(&repo, entry)).transpose()
+    };
+    entries.push(GitConflictEntry {
+      ancestor: side(&conflict.ancestor)?,
+      ours: side(&conflict.our)?,
+      theirs: side(&conflict.their)?,
+      merged: if merged.unwrap_or(false) {
+        merged_conflict_text(&repo, &conflict)?
+      } else {
+        None
+      },
+      path,
+    });
+  }
+  Ok(entries)
+}
+
+/// Resolves a conflicted path by taking one side, or `content` for `Custom`,
+/// writing it to the worktree and staging it. Taking a side that deleted the
+/// file removes it.
+#[tauri::command]
+pub fn git_resolve_conflict(
+  repo_path: String,
+  path: String,
+  resolution: GitConflictResolution,
+  content: Option<String>,
+) -> Result<(), GitError> {
+  let repo = open_repo_at(&repo_path)?;
+  let file = worktree_file(&repo, &path)?;
+  let mut index = repo.index()?;
+  let conflict = index.conflict_get(Path::new(&path)).map_err(|err| {
+    if err.code() == ErrorCode::NotFound {
+      GitError::new(GitErrorCode::InvalidArgument, format!("{} is not conflicted", path))
+    } else {
+      err.into()
+    }
+  })?;
+
+  let resolved = match resolution {
+    GitConflictResolution::Ours => conflict.our.map(|entry| {
+      (entry.mode, repo.find_blob(entry.id).map(|blob| blob.content().to_vec()))
+    }),
+    GitConflictResolution::Theirs => conflict.their.map(|entry| {
+      (entry.mode, repo.find_blob(entry.id).map(|blob| blob.content().to_vec()))
+    }),
+    GitConflictResolution::Custom => {
+      let content = content.ok_or_else(|| {
+        GitError::new(GitErrorCode::InvalidArgument, "custom resolution requires `content`")
+      })?;
+      let mode = conflict
+        .our
+        .or(conflict.their)
+        .map(|entry| entry.mode)
+        .unwrap_or(0o100644);
+      Some((mode, Ok(content.into_bytes())))
+    }
+  };
+
+  index.conflict_remove(Path::new(&path))?;
+  // Checked without following links, so a conflicted symlink is replaced
+  // rather than written through.
+  let present = std::fs::symlink_metadata(&file).is_ok_and(|metadata| !metadata.is_dir());
+  match resolved {
+    Some((mode, content)) => {
+      let content = content?;
+      if let Some(parent) = file.parent() {
+        std::fs::create_dir_all(parent)?;
+      }
+      if present {
+        std::fs::remove_file(&file)?;
+      }
+      if mode == 0o120000 {
+        write_symlink(&file, &content)?;
+      } else {
+        std::fs::write(&file, &content)?;
+        set_executable(&file, mode == 0o100755)?;
+      }
+      // Staged from the resolved content and mode rather than the disk, which
+      // may not be able to represent a symlink.
+      index.add_frombuffer(
+        &IndexEntry {
+          ctime: IndexTime::new(0, 0),
+          mtime: IndexTime::new(0, 0),
+          dev: 0,
+          ino: 0,
+          mode,
+          uid: 0,
+          gid: 0,
+          file_size: 0,
+          id: Oid::zero(),
+          flags: path.len().min(0xfff) as u16,
+          flags_extended: 0,
+          path: path.as_bytes().to_vec(),
+        },
+        &content,
+      )?;
+    }
+    None => {
+      if present {
+        std::fs::remove_file(&file)?;
+      }
+      index.remove_path(Path::new(&path))?;
+    }
+  }
+  index.write().map_err(GitError::from)
+}
+
+/// Saves local changes to a new stash entry and returns its oid. With `paths`,
+/// only matching files are stashed. If a step after saving fails, such as
+/// putting back the other files' changes, the error names the saved stash in
+/// `details.stash`.
+#[tauri::command]
+pub fn git_stash_push(
+  repo_path: String,
+  message: Option<String>,
+  include_untracked: Option<bool>,
+  keep_index: Option<bool>,
+  paths: Option<Vec<String>>,
+) -> Result<String, GitError> {
+  let mut repo = open_repo_at(&repo_path)?;
+  let mut flags = StashFlags::DEFAULT;
+  if include_untracked.unwrap_or(false) {
+    flags |= StashFlags::INCLUDE_UNTRACKED;
+  }
+  if keep_index.unwrap_or(false) {
+    flags |= StashFlags::KEEP_INDEX;
+  }
+  let stasher = signature_or_default(&repo)?;
+  let nothing_to_stash = |err: git2::Error| {
+    if err.code() == ErrorCode::NotFound {
+      GitError::new(GitErrorCode::NothingToCommit, "no local changes to stash")
+    } else {
+      err.into()
+    }
+  };
+  let paths = match paths.filter(|paths| !paths.is_empty()) {
+    Some(paths) => paths,
+    None => {
+      return repo
+        .stash_save2(&stasher, message.as_deref(), Some(flags))
+        .map(|oid| oid.to_string())
+        .map_err(nothing_to_stash);
+    }
+  };
+
+  // libgit2 only stashes the matching paths but still resets the whole
+  // worktree, so everything else is snapshotted and put back afterwards.
+  let pathspec = Pathspec::new(paths.iter())?;
+  let kept = local_changes_outside(&repo, &pathspec)?;
+  let mut options = StashSaveOptions::new(stasher.clone());
+  options.flags(Some(flags));
+  for path in &paths {
+    options.pathspec(path.as_str());
+  }
+  let stashed = repo.stash_save_ext(Some(&mut options)).map_err(nothing_to_stash)?;
+  // Put the other edits back before anything else can fail.
+  restore_local_changes(&repo, kept).map_err(|err| stash_saved_error(stashed, err))?;
+
+  // The options have no message field, so the entry is named, and replaced if
+  // it had to be fixed up, through its reflog.
+  let oid = fix_path_limited_stash(&repo, stashed, &pathspec)
+    .map_err(|err| stash_saved_error(stashed, err))?;
+  if oid != stashed || message.is_some() {
+    replace_stash_entry(&repo, stashed, oid, &stasher, message.as_deref())
+      .map_err(|err| stash_saved_error(stashed, err))?;
+  }
+  Ok(oid.to_string())
+}
+
+/// Fixes up stash `oid` saved with untracked files and a pathspec. libgit2 saves
+/// untracked files outside the pathspec too, and records the matching ones in
+/// the worktree tree as well as the untracked one, which git refuses to apply.
+/// Returns the rewritten stash commit, or `oid` if nothing needed fixing.
+fn fix_path_limited_stash(
+  repo: &Repository,
+  oid: Oid,
+  pathspec: &Pathspec,
+) -> Result<Oid, GitError> {
+  let stash = repo.find_commit(oid)?;
+  // The third parent holds the untracked files.
+  if stash.parent_count() < 3 {
+    return Ok(oid);
+  }
+  let untracked = stash.parent(2)?;
+  let mut untracked_index = git2::Index::new()?;
+  untracked_index.read_tree(&untracked.tree()?)?;
+  let mut worktree_index = git2::Index::new()?;
+  worktree_index.read_tree(&stash.tree()?)?;
+  let paths: Vec<PathBuf> = untracked_index
+    .iter()
+    .map(|entry| PathBuf::from(String::from_utf8_lossy(&entry.path).into_owned()))
+    .collect();
+  let mut changed = false;
+  for path in &paths {
+    if worktree_index.get_path(path, 0).is_some() {
+      worktree_index.remove(path, 0)?;
+      changed = true;
+    }
+    if !pathspec.matches_path(path, PathspecFlags::DEFAULT) {
+      untracked_index.remove(path, 0)?;
+      changed = true;
+    }
+  }
+  if !changed {
+    return Ok(oid);
+  }
+  let mut parents = vec![stash.parent(0)?, stash.parent(1)?];
+  if !untracked_index.is_empty() {
+    let tree = repo.find_tree(untracked_index.write_tree_to(repo)?)?;
+    let commit = repo.commit(
+      None,
+      &untracked.author(),
+      &untracked.committer(),
+      &String::from_utf8_lossy(untracked.message_bytes()),
+      &tree,
+      &[],
+    )?;
+    parents.push(repo.find_commit(commit)?);
+  }
+  let parents: Vec<&git2::Commit> = parents.iter().collect();
+  let tree = repo.find_tree(worktree_index.write_tree_to(repo)?)?;
+  let rewritten = repo.commit(
+    None,
+    &stash.author(),
+    &stash.committer(),
+    &String::from_utf8_lossy(stash.message_bytes()),
+    &tree,
+    &parents,
+  )?;
+  Ok(rewritten)
+}
+
+/// Replaces the newest stash entry, `stashed`, with `oid`, renaming it to
+/// `message` if given.
+fn replace_stash_entry(
+  repo: &Repository,
+  stashed: Oid,
+  oid: Oid,
+  stasher: &Signature,
+  message: Option<&str>,
+) -> Result<(), GitError> {
+  let mut reflog = repo.reflog("refs/stash")?;
+  let message = match message {
+    Some(value) => value.to_string(),
+    None => reflog
+      .get(0)
+      .and_then(|entry| entry.message().map(str::to_string))
+      .unwrap_or_default(),
+  };
+  if oid != stashed {
+    repo.find_reference("refs/stash")?.set_target(oid, &message)?;
+    reflog = repo.reflog("refs/stash")?;
+    // Updating the ref may have logged the move on top of the saved entry.
+    if reflog.get(0).map(|entry| entry.id_new()) == Some(oid) {
+      reflog.remove(0, false)?;
     }
   }
+  reflog.remove(0, false)?;
+  reflog.append(oid, stasher, Some(&message))?;
+  reflog.write().map_err(GitError::from)
+}
+
+/// Reports a step after saving stash `oid` failing, naming the stash so the
+/// caller can still find, apply or drop it.
+fn stash_saved_error(oid: Oid, err: GitError) -> GitError {
+  let mut details = match err.details {
+    Some(serde_json::Value::Object(map)) => map,
+    _ => serde_json::Map::new(),
+  };
+  details.insert("stash".to_string(), serde_json::json!(oid.to_string()));
+  GitError::new(err.code, format!("saved stash {}, but {}", oid, err.message))
+    .with_details(serde_json::Value::Object(details))
+}
+
+#[tauri::command]
+pub fn git_stash_list(repo_path: String) -> Result<Vec<GitStashEntry>, GitError> {
+  let mut repo = open_repo_at(&repo_path)?;
+  let mut entries = Vec::new();
+  repo.stash_foreach(|index, message, oid| {
+    entries.push(GitStashEntry {
+      index,
+      message: message.to_string(),
+      oid: oid.to_string(),
+    });
+    true
+  })?;
   Ok(entries)
 }
 
+/// Applies stash entry `index` (default 0) and keeps it. Local edits that the
+/// stash would overwrite make this fail without touching the worktree.
+#[tauri::command]
+pub fn git_stash_apply(
+  repo_path: String,
+  index: Option<usize>,
+  reinstate_index: Option<bool>,
+) -> Result<(), GitError> {
+  let mut repo = open_repo_at(&repo_path)?;
+  let mut options = stash_apply_options(reinstate_index);
+  repo
+    .stash_apply(index.unwrap_or(0), Some(&mut options))
+    .map_err(GitError::from)
+}
+
+/// Like `git_stash_apply`, then drops the entry if it applied cleanly.
+#[tauri::command]
+pub fn git_stash_pop(
+  repo_path: String,
+  index: Option<usize>,
+  reinstate_index: Option<bool>,
+) -> Result<(), GitError> {
+  let mut repo = open_repo_at(&repo_path)?;
+  let mut options = stash_apply_options(reinstate_index);
+  repo
+    .stash_pop(index.unwrap_or(0), Some(&mut options))
+    .map_err(GitError::from)
+}
+
+#[tauri::command]
+pub fn git_stash_drop(repo_path: String, index: Option<usize>) -> Result<(), GitError> {
+  let mut repo = open_repo_at(&repo_path)?;
+  repo.stash_drop(index.unwrap_or(0)).map_err(GitError::from)
+}
+
+/// Reads `path` as of `rev`. UTF-8 text is returned as-is; anything else
+/// (binary or otherwise undecodable) is base64-encoded.
+#[tauri::command]
+pub fn git_show_file(
+  repo_path: String,
+  rev: String,
+  path: String,
+) -> Result<GitFileContent, GitError> {
+  validate_revspec(&rev)?;
+  let repo = open_repo_at(&repo_path)?;
+  let tree = resolve_tree(&repo, &rev)?;
+  let entry = tree_entry_by_path(&tree, &path)?;
+  if entry.kind() != Some(ObjectType::Blob) {
+    return Err(GitError::new(
+      GitErrorCode::InvalidArgument,
+      format!("{} is not a file", path),
+    ));
+  }
+  let blob = repo.find_blob(entry.id())?;
+  let binary = blob.is_binary();
+  let text = if binary {
+    None
+  } else {
+    std::str::from_utf8(blob.content()).ok()
+  };
+  let (content, encoding) = match text {
+    Some(text) => (text.to_string(), GitContentEncoding::Utf8),
+    None => (BASE64.encode(blob.content()), GitContentEncoding::Base64),
+  };
+  Ok(GitFileContent {
+    path,
+    oid: blob.id().to_string(),
+    mode: entry.filemode(),
+    size: blob.size(),
+    binary,
+    content,
+    encoding,
+  })
+}
+
+/// Lists the entries of directory `path` (default: the root) as of `rev`.
 #[tauri::command]
-pub fn git_changed_files(repo_path: String) -> Result<Vec<String>, String> {
-  let entries = git_status(repo_path)?;
-  Ok(entries.into_iter().map(|entry| entry.path).collect())
+pub fn git_list_tree(
+  repo_path: String,
+  rev: String,
+  path: Option<String>,
+) -> Result<Vec<GitTreeEntry>, GitError> {
+  validate_revspec(&rev)?;
+  let repo = open_repo_at(&repo_path)?;
+  let root = resolve_tree(&repo, &rev)?;
+  let dir = path.as_deref().map(|value| value.trim_end_matches('/')).unwrap_or("");
+  let tree = if dir.is_empty() {
+    root
+  } else {
+    let entry = tree_entry_by_path(&root, dir)?;
+    if entry.kind() != Some(ObjectType::Tree) {
+      return Err(GitError::new(
+        GitErrorCode::InvalidArgument,
+        format!("{} is not a directory", dir),
+      ));
+    }
+    repo.find_tree(entry.id())?
+  };
+  let odb = repo.odb()?;
+  let mut entries = Vec::new();
+  for entry in tree.iter() {
+    let name = String::from_utf8_lossy(entry.name_bytes()).to_string();
+    let kind = tree_entry_kind(entry.kind());
+    let size = match kind {
+      GitTreeEntryKind::Blob => Some(odb.read_header(entry.id())?.0),
+      _ => None,
+    };
+    entries.push(GitTreeEntry {
+      path: if dir.is_empty() {
+        name.clone()
+      } else {
+        format!("{}/{}", dir, name)
+      },
+      name,
+      kind,
+      mode: entry.filemode(),
+      oid: entry.id().to_string(),
+      size,
+    });
+  }
+  Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::TestRepo;
+
+  /// `main` and `feature` both change `a.txt`; `feature` then adds `c.txt`.
+  fn diverged() -> (TestRepo, Oid, Oid) {
+    let test = TestRepo::new();
+    test.write("a.txt", "base\n");
+    let base = test.commit("base");
+    test.checkout("feature", base);
+    test.write("a.txt", "feature\n");
+    let first = test.commit("change a");
+    test.write("c.txt", "c\n");
+    let second = test.commit("add c");
+    test.checkout("main", base);
+    test.write("a.txt", "main\n");
+    test.commit("main change");
+    (test, first, second)
+  }
+
+  fn expect_err<T>(result: Result<T, GitError>) -> GitError {
+    match result {
+      Ok(_) => panic!("expected an error"),
+      Err(err) => err,
+    }
+  }
+
+  fn cherry_pick(test: &TestRepo, commits: &[Oid]) -> GitCherryPickResult {
+    let commits = commits.iter().map(Oid::to_string).collect();
+    git_cherry_pick(test.path(), commits, None, None).unwrap()
+  }
+
+  #[test]
+  fn cherry_pick_conflict_records_state_and_continues() {
+    let (test, first, second) = diverged();
+    let result = cherry_pick(&test, &[first, second]);
+    assert!(matches!(result.status, GitCherryPickStatus::Conflicts));
+    assert_eq!(result.failed, Some(first.to_string()));
+    assert_eq!(result.conflicts, vec!["a.txt".to_string()]);
+    assert_eq!(result.remaining, vec![second.to_string()]);
+    assert!(test.repo.path().join("CHERRY_PICK_HEAD").exists());
+    assert!(test.repo.path().join(CHERRY_PICK_STATE).exists());
+
+    let err = expect_err(git_cherry_pick(test.path(), vec![second.to_string()], None, None));
+    assert!(matches!(err.code, GitErrorCode::InvalidArgument));
+    let err = expect_err(git_cherry_pick(
+      test.path(),
+      Vec::new(),
+      None,
+      Some(GitCherryPickAction::Continue),
+    ));
+    assert!(matches!(err.code, GitErrorCode::Conflict));
+
+    git_resolve_conflict(test.path(), "a.txt".into(), GitConflictResolution::Theirs, None)
+      .unwrap();
+    let result =
+      git_cherry_pick(test.path(), Vec::new(), None, Some(GitCherryPickAction::Continue)).unwrap();
+    assert!(matches!(result.status, GitCherryPickStatus::Picked));
+    assert_eq!(result.picked.len(), 2);
+    assert_eq!(test.read("a.txt"), "feature\n");
+    assert_eq!(test.read("c.txt"), "c\n");
+    let head = test.repo.head().unwrap().peel_to_commit().unwrap();
+    assert_eq!(head.summary(), Some("add c"));
+    assert_eq!(head.parent(0).unwrap().summary(), Some("change a"));
+    assert!(!test.repo.path().join("CHERRY_PICK_HEAD").exists());
+    assert!(!test.repo.path().join(CHERRY_PICK_STATE).exists());
+  }
+
+  #[test]
+  fn cherry_pick_skip_moves_on_to_remaining() {
+    let (test, first, second) = diverged();
+    cherry_pick(&test, &[first, second]);
+    let result =
+      git_cherry_pick(test.path(), Vec::new(), None, Some(GitCherryPickAction::Skip)).unwrap();
+    assert!(matches!(result.status, GitCherryPickStatus::Picked));
+    assert_eq!(result.picked.len(), 1);
+    assert_eq!(test.read("a.txt"), "main\n");
+    assert_eq!(test.read("c.txt"), "c\n");
+    assert!(!test.repo.index().unwrap().has_conflicts());
+  }
+
+  #[test]
+  fn cherry_pick_abort_restores_original_head() {
+    let (test, first, second) = diverged();
+    let base = test.repo.head().unwrap().target().unwrap();
+    test.write("other.txt", "other\n");
+    test.commit("other");
+    let orig_head = test.repo.head().unwrap().target().unwrap();
+    let result = cherry_pick(&test, &[second, first]);
+    assert_eq!(result.picked.len(), 1);
+    assert_eq!(result.failed, Some(first.to_string()));
+    assert_ne!(test.repo.head().unwrap().target().unwrap(), orig_head);
+
+    let result =
+      git_cherry_pick(test.path(), Vec::new(), None, Some(GitCherryPickAction::Abort)).unwrap();
+    assert!(matches!(result.status, GitCherryPickStatus::Aborted));
+    assert_eq!(test.repo.head().unwrap().target().unwrap(), orig_head);
+    assert_ne!(orig_head, base);
+    assert_eq!(test.read("a.txt"), "main\n");
+    assert!(!test.root().join("c.txt").exists());
+    assert!(!test.repo.index().unwrap().has_conflicts());
+    assert!(!test.repo.path().join(CHERRY_PICK_STATE).exists());
+    let err =
+      expect_err(git_cherry_pick(test.path(), Vec::new(), None, Some(GitCherryPickAction::Abort)));
+    assert!(matches!(err.code, GitErrorCode::InvalidArgument));
+  }
+
+  #[test]
+  fn cherry_pick_over_local_changes_records_nothing() {
+    let (test, first, second) = diverged();
+    test.write("a.txt", "local\n");
+    let result = cherry_pick(&test, &[first, second]);
+    assert!(matches!(result.status, GitCherryPickStatus::Conflicts));
+    assert_eq!(result.conflicts, vec!["a.txt".to_string()]);
+    assert_eq!(result.remaining, vec![second.to_string()]);
+    assert_eq!(test.read("a.txt"), "local\n");
+    assert!(!test.repo.path().join("CHERRY_PICK_HEAD").exists());
+    assert!(!test.repo.path().join(CHERRY_PICK_STATE).exists());
+  }
+
+  #[test]
+  fn stash_push_with_paths_keeps_other_changes() {
+    let test = TestRepo::new();
+    test.write("a.txt", "a\n");
+    test.write("b.txt", "b\n");
+    test.commit("first");
+    test.write("a.txt", "a2\n");
+    test.write("b.txt", "b2\n");
+    test.write("new.txt", "new\n");
+    let oid = git_stash_push(
+      test.path(),
+      Some("only a".into()),
+      Some(true),
+      None,
+      Some(vec!["a.txt".into()]),
+    )
+    .unwrap();
+    assert_eq!(test.read("a.txt"), "a\n");
+    assert_eq!(test.read("b.txt"), "b2\n");
+    assert_eq!(test.read("new.txt"), "new\n");
+    let entries = git_stash_list(test.path()).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].message, "only a");
+    assert_eq!(entries[0].oid, oid);
+    // `new.txt` didn't match, so it stays out of the stash's untracked files.
+    let stash = test.repo.find_commit(Oid::from_str(&oid).unwrap()).unwrap();
+    assert_eq!(stash.parent_count(), 2);
+
+    git_stash_pop(test.path(), None, None).unwrap();
+    assert_eq!(test.read("a.txt"), "a2\n");
+    assert_eq!(test.read("b.txt"), "b2\n");
+    assert_eq!(test.read("new.txt"), "new\n");
+
+    let oid = git_stash_push(test.path(), None, Some(true), None, Some(vec!["new.txt".into()]))
+      .unwrap();
+    assert!(!test.root().join("new.txt").exists());
+    assert_eq!(test.read("a.txt"), "a2\n");
+    let entries = git_stash_list(test.path()).unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].oid, oid);
+    assert!(!entries[0].message.is_empty());
+    let stash = test.repo.find_commit(Oid::from_str(&oid).unwrap()).unwrap();
+    assert!(stash.tree().unwrap().get_name("new.txt").is_none());
+    git_stash_pop(test.path(), None, None).unwrap();
+    assert_eq!(test.read("new.txt"), "new\n");
+  }
+
+  #[test]
+  fn restore_local_changes_reports_the_paths_it_could_not_restore() {
+    let test = TestRepo::new();
+    test.write("blocker", "file\n");
+    test.commit("first");
+    let change = |path: &str| LocalChange {
+      path: path.to_string(),
+      content: Some(b"kept\n".to_vec()),
+      executable: false,
+      entry: None,
+    };
+    // `blocker` is a file, so nothing can be created under it.
+    let changes = vec![change("blocker/inner.txt"), change("kept.txt")];
+    let err = expect_err(restore_local_changes(&test.repo, changes));
+    assert_eq!(err.details, Some(serde_json::json!({ "paths": ["blocker/inner.txt"] })));
+    assert_eq!(test.read("kept.txt"), "kept\n");
+
+    let oid = Oid::hash_object(ObjectType::Blob, b"stash").unwrap();
+    let err = stash_saved_error(oid, err);
+    assert!(err.message.contains(&oid.to_string()));
+    let details = err.details.unwrap();
+    assert_eq!(details["stash"], serde_json::json!(oid.to_string()));
+    assert_eq!(details["paths"], serde_json::json!(["blocker/inner.txt"]));
+  }
+
+  /// Commits `a.txt`, `inside/b.txt` and `outside/c.txt`, then changes all three
+  /// in a second commit, leaving HEAD and the worktree at the first.
+  fn two_commits() -> (TestRepo, Oid) {
+    let test = TestRepo::new();
+    test.write("a.txt", "a\n");
+    test.write("inside/b.txt", "b\n");
+    test.write("outside/c.txt", "c\n");
+    let first = test.commit("first");
+    test.write("a.txt", "a2\n");
+    test.write("inside/b.txt", "b2\n");
+    test.write("outside/c.txt", "c2\n");
+    let second = test.commit("second");
+    test.checkout("main", first);
+    (test, second)
+  }
+
+  fn checkout_commit(test: &TestRepo, oid: Oid) -> Result<(), GitError> {
+    let repo = Repository::open(test.root()).unwrap();
+    let tree = repo.find_commit(oid).unwrap().tree().unwrap();
+    checkout_tree_safe(&repo, &tree)
+  }
+
+  #[test]
+  fn checkout_tree_safe_updates_a_full_worktree() {
+    let (test, second) = two_commits();
+    test.write("a.txt", "local\n");
+    assert!(matches!(expect_err(checkout_commit(&test, second)).code, GitErrorCode::Conflict));
+    assert_eq!(test.read("a.txt"), "local\n");
+    assert_eq!(test.read("inside/b.txt"), "b\n");
+
+    test.write("a.txt", "a\n");
+    checkout_commit(&test, second).unwrap();
+    assert_eq!(test.read("a.txt"), "a2\n");
+    assert_eq!(test.read("inside/b.txt"), "b2\n");
+    assert_eq!(test.read("outside/c.txt"), "c2\n");
+
+    // Without a cone, applying the sparse settings leaves everything in place.
+    let repo = Repository::open(test.root()).unwrap();
+    apply_sparse_checkout(&repo, None).unwrap();
+    assert_eq!(test.read("outside/c.txt"), "c2\n");
+    assert!(skip_worktree_paths(&repo.index().unwrap()).is_empty());
+  }
+
+  #[test]
+  fn checkout_tree_safe_keeps_a_sparse_worktree_sparse() {
+    let (test, second) = two_commits();
+    git_sparse_checkout_set(test.path(), vec!["inside".into()]).unwrap();
+    assert!(!test.root().join("outside").exists());
+    assert_eq!(test.read("a.txt"), "a\n");
+
+    checkout_commit(&test, second).unwrap();
+    assert_eq!(test.read("a.txt"), "a2\n");
+    assert_eq!(test.read("inside/b.txt"), "b2\n");
+    assert!(!test.root().join("outside").exists());
+    let repo = Repository::open(test.root()).unwrap();
+    let index = repo.index().unwrap();
+    let expected = repo.find_commit(second).unwrap().tree().unwrap();
+    let expected = expected.get_path(Path::new("outside/c.txt")).unwrap().id();
+    assert_eq!(index.get_path(Path::new("outside/c.txt"), 0).unwrap().id, expected);
+    assert!(skip_worktree_paths(&index).contains(Path::new("outside/c.txt")));
+
+    git_sparse_checkout_disable(test.path()).unwrap();
+    assert_eq!(test.read("outside/c.txt"), "c2\n");
+  }
 }
@@ -5,13 +5,18 @@ use std::io::{BufRead, BufReader, Write};
 use std::os::unix::fs::PermissionsExt;
 #[cfg(unix)]
 use std::os::unix::net::{UnixListener, UnixStream};
+use std::collections::HashSet;
 use std::path::PathBuf;
-use std::sync::OnceLock;
+use std::sync::{Arc, Mutex, OnceLock};
 use std::thread;
 use tauri::{AppHandle, Manager};
 
+use crate::checkpoint;
+use crate::deps;
+use crate::pool;
 use crate::diff;
-use crate::git;
+use crate::git::{self, GitError, GitErrorCode};
+use crate::watch;
 
 const GIT_RPC_SOCKET_NAME: &str = "git-rpc.sock";
 
@@ -29,7 +34,14 @@ struct RpcResponse<T> {
   id: String,
   ok: bool,
   result: Option<T>,
-  error: Option<String>,
+  error: Option<GitError>,
+}
+
+/// Server-initiated message; unlike responses it has no `id`.
+#[derive(Serialize)]
+struct RpcNotification<'a, T> {
+  method: &'a str,
+  params: T,
 }
 
 #[derive(Deserialize)]
@@ -63,8 +75,9 @@ struct CreateBranchParam {
 struct CreateWorktreeParam {
   repo_path: String,
   path: String,
-  branch: String,
+  branch: Option<String>,
   create_branch: Option<bool>,
+  options: Option<git::GitWorktreeOptions>,
 }
 
 #[derive(Deserialize)]
@@ -89,14 +102,330 @@ struct ResetHardParam {
   git_ref: String,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PathsParam {
+  repo_path: String,
+  #[serde(default)]
+  paths: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CommitParam {
+  repo_path: String,
+  message: String,
+  author: Option<git::GitSignature>,
+  committer: Option<git::GitSignature>,
+  allow_empty: Option<bool>,
+  amend: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GitDiffParam {
+  repo_path: String,
+  mode: git::GitDiffMode,
+  from: Option<String>,
+  to: Option<String>,
+  paths: Option<Vec<String>>,
+  context_lines: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LogParam {
+  repo_path: String,
+  rev: Option<String>,
+  paths: Option<Vec<String>>,
+  author: Option<String>,
+  limit: Option<usize>,
+  offset: Option<usize>,
+  first_parent: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MergeParam {
+  repo_path: String,
+  branch: String,
+  mode: git::GitMergeMode,
+  message: Option<String>,
+  dry_run: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CherryPickParam {
+  repo_path: String,
+  #[serde(default)]
+  commits: Vec<String>,
+  mainline: Option<u32>,
+  action: Option<git::GitCherryPickAction>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RebaseParam {
+  repo_path: String,
+  action: git::GitRebaseAction,
+  upstream: Option<String>,
+  onto: Option<String>,
+  branch: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConflictsParam {
+  repo_path: String,
+  paths: Option<Vec<String>>,
+  merged: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ResolveConflictParam {
+  repo_path: String,
+  path: String,
+  resolution: git::GitConflictResolution,
+  content: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StashPushParam {
+  repo_path: String,
+  message: Option<String>,
+  include_untracked: Option<bool>,
+  keep_index: Option<bool>,
+  paths: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StashApplyParam {
+  repo_path: String,
+  index: Option<usize>,
+  reinstate_index: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StashDropParam {
+  repo_path: String,
+  index: Option<usize>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ShowFileParam {
+  repo_path: String,
+  rev: String,
+  path: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListTreeParam {
+  repo_path: String,
+  rev: String,
+  path: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckpointCreateParam {
+  repo_path: String,
+  attempt: String,
+  turn: Option<u32>,
+  message: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckpointListParam {
+  repo_path: String,
+  attempt: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckpointDiffParam {
+  repo_path: String,
+  attempt: String,
+  from: u32,
+  to: Option<u32>,
+  context_lines: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckpointParam {
+  repo_path: String,
+  attempt: String,
+  turn: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckpointDeleteParam {
+  repo_path: String,
+  attempt: String,
+  turn: Option<u32>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StatusParam {
+  repo_path: String,
+  include_ignored: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompareRefsParam {
+  repo_path: String,
+  base: String,
+  head: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompareRefsBatchParam {
+  repo_path: String,
+  base: String,
+  heads: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListBranchesParam {
+  repo_path: String,
+  kind: Option<git::GitBranchKind>,
+  pattern: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RenameBranchParam {
+  repo_path: String,
+  branch: String,
+  new_name: String,
+  force: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetBranchTargetParam {
+  repo_path: String,
+  branch: String,
+  target: String,
+  expected_oid: Option<String>,
+  force: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetUpstreamParam {
+  repo_path: String,
+  branch: String,
+  upstream: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListWorktreesParam {
+  repo_path: String,
+  include_status: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WorktreePathParam {
+  worktree_path: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LockWorktreeParam {
+  worktree_path: String,
+  reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct MoveWorktreeParam {
+  worktree_path: String,
+  new_path: String,
+  force: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RepairWorktreesParam {
+  repo_path: String,
+  paths: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PruneWorktreesParam {
+  repo_path: String,
+  dry_run: Option<bool>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PoolConfigureParam {
+  repo_path: String,
+  size: usize,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SparseCheckoutParam {
+  worktree_path: String,
+  paths: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CloneDepsParam {
+  source_path: String,
+  target_path: String,
+  paths: Vec<String>,
+  options: Option<deps::GitCloneDepsOptions>,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct DiffRequest {
   original: String,
   modified: String,
   context_lines: Option<usize>,
+  #[serde(default)]
+  options: diff::DiffOptions,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StructuredDiffRequest {
+  original: String,
+  modified: String,
+  context_lines: Option<usize>,
+  #[serde(default)]
+  options: diff::DiffOptions,
+  inline: Option<diff::InlineGranularity>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WatchRemoveParam {
+  watch_id: String,
+}
+
+type RpcWriter = Arc<Mutex<UnixStream>>;
+
 #[allow(dead_code)]
 pub fn active_socket_path() -> Option<PathBuf> {
   RPC_SOCKET_PATH.get().cloned()
@@ -126,12 +455,14 @@ pub fn start_git_rpc_server(app: &AppHandle) -> Result<PathBuf, String> {
 
   // No limit on concurrent connections - each spawns a new thread. Fine for a local
   // single-user app. A thread pool (e.g., rayon) could be added if this becomes an issue.
+  let app = app.clone();
   thread::spawn(move || {
     for stream in listener.incoming() {
       match stream {
         Ok(stream) => {
-          thread::spawn(|| {
-            handle_stream(stream);
+          let app = app.clone();
+          thread::spawn(move || {
+            handle_stream(stream, app);
           });
         }
         Err(err) => {
@@ -161,41 +492,151 @@ fn git_rpc_socket_path(app: &AppHandle) -> Result<PathBuf, String> {
   Ok(dir.join(GIT_RPC_SOCKET_NAME))
 }
 
-// Request-per-connection design: each connection handles exactly one request then closes.
-// This simplifies the protocol (no framing/multiplexing) and client implementation.
-// Clients must open a new connection for each RPC call.
-fn handle_stream(stream: UnixStream) {
-  let reader = BufReader::new(&stream);
-  let mut writer = &stream;
+// Connections are persistent: every newline-delimited request is handled on its own
+// thread and its response is written back as soon as it completes, so responses may
+// arrive out of order and clients match them by `id`. One-shot clients that close
+// the socket after their first response keep working unchanged.
+//
+// `watch_add` subscriptions stream `fs/watch` notifications over the same connection
+// and are removed when it closes. `git_clone_deps` likewise streams its progress
+// as notifications ahead of its response.
+fn handle_stream(stream: UnixStream, app: AppHandle) {
+  let writer: RpcWriter = match stream.try_clone() {
+    Ok(writer) => Arc::new(Mutex::new(writer)),
+    Err(err) => {
+      eprintln!("[git-rpc] failed to clone stream: {}", err);
+      return;
+    }
+  };
+  let reader = BufReader::new(stream);
+  let mut watches = HashSet::new();
 
   for line in reader.lines() {
     let line = match line {
       Ok(line) => line,
       Err(err) => {
         eprintln!("[git-rpc] read failed: {}", err);
-        return;
+        break;
       }
     };
     if line.trim().is_empty() {
       continue;
     }
 
-    let response = match serde_json::from_str::<RpcRequest>(&line) {
-      Ok(request) => handle_request(request),
-      Err(err) => RpcResponse::<serde_json::Value> {
-        id: "unknown".to_string(),
-        ok: false,
-        result: None,
-        error: Some(err.to_string()),
-      },
+    let request = match serde_json::from_str::<RpcRequest>(&line) {
+      Ok(request) => request,
+      Err(err) => {
+        write_message(
+          &writer,
+          &RpcResponse::<serde_json::Value> {
+            id: "unknown".to_string(),
+            ok: false,
+            result: None,
+            error: Some(GitError::new(GitErrorCode::InvalidParams, err.to_string())),
+          },
+        );
+        continue;
+      }
     };
 
-    if let Ok(payload) = serde_json::to_string(&response) {
-      if writer.write_all(payload.as_bytes()).is_ok() {
-        let _ = writer.write_all(b"\n");
-      }
+    // Watch requests are handled inline so a subscription can't be registered
+    // after the connection has already been cleaned up.
+    if matches!(request.method.as_str(), "watch_add" | "watch_remove") {
+      let response = handle_watch_request(&app, &writer, &mut watches, request);
+      write_message(&writer, &response);
+      continue;
     }
+
+    let writer = Arc::clone(&writer);
+    thread::spawn(move || {
+      let response = if request.method == "git_clone_deps" {
+        handle_clone_deps_request(&writer, request)
+      } else {
+        handle_request(request)
+      };
+      write_message(&writer, &response);
+    });
+  }
+
+  let manager = app.state::<watch::WatchManager>();
+  for watch_id in watches {
+    let _ = manager.remove_watch(&watch_id);
+  }
+}
+
+fn handle_watch_request(
+  app: &AppHandle,
+  writer: &RpcWriter,
+  watches: &mut HashSet<String>,
+  request: RpcRequest,
+) -> RpcResponse<serde_json::Value> {
+  let manager = app.state::<watch::WatchManager>();
+  let result = match request.method.as_str() {
+    "watch_add" => {
+      parse_and_execute::<watch::WatchAddRequest, _>(request.params, |p| {
+        let writer = Arc::clone(writer);
+        let sink = watch::WatchSink::Callback(Arc::new(move |payload| {
+          write_message(
+            &writer,
+            &RpcNotification {
+              method: watch::EVENT_NAME,
+              params: payload,
+            },
+          );
+        }));
+        let response = manager
+          .add_watch(sink, p)
+          .map_err(|message| GitError::new(GitErrorCode::InvalidArgument, message))?;
+        watches.insert(response.watch_id.clone());
+        Ok(serde_json::to_value(response).unwrap_or_default())
+      })
+    }
+    "watch_remove" => {
+      parse_and_execute::<WatchRemoveParam, _>(request.params, |p| {
+        // Connections can only remove the watches they subscribed to.
+        if !watches.remove(&p.watch_id) {
+          return Err(GitError::new(GitErrorCode::InvalidArgument, "watch not found"));
+        }
+        manager
+          .remove_watch(&p.watch_id)
+          .map(|_| serde_json::Value::Null)
+          .map_err(|message| GitError::new(GitErrorCode::Other, message))
+      })
+    }
+    _ => Err(GitError::new(GitErrorCode::UnknownMethod, "unknown_method")),
+  };
+  to_response(request.id, result)
+}
+
+fn handle_clone_deps_request(
+  writer: &RpcWriter,
+  request: RpcRequest,
+) -> RpcResponse<serde_json::Value> {
+  let result = parse_and_execute::<CloneDepsParam, _>(request.params, |p| {
+    let options = p.options.unwrap_or_default();
+    deps::clone_deps(&p.source_path, &p.target_path, &p.paths, options, &|progress| {
+      write_message(
+        writer,
+        &RpcNotification {
+          method: deps::PROGRESS_EVENT,
+          params: progress,
+        },
+      );
+    })
+    .map(|value| serde_json::to_value(value).unwrap_or_default())
+  });
+  to_response(request.id, result)
+}
+
+fn write_message<T: Serialize>(writer: &Mutex<UnixStream>, message: &T) {
+  let Ok(mut payload) = serde_json::to_string(message) else {
     return;
+  };
+  payload.push('\n');
+  // The lock keeps concurrent messages from interleaving mid-line. Write errors
+  // mean the client went away, which the read loop notices on its own.
+  if let Ok(mut stream) = writer.lock() {
+    let _ = stream.write_all(payload.as_bytes());
   }
 }
 
@@ -228,6 +669,12 @@ fn handle_request(request: RpcRequest) -> RpcResponse<serde_json::Value> {
           .map(|value| serde_json::Value::Bool(value))
       })
     }
+    "git_list_branches" => {
+      parse_and_execute::<ListBranchesParam, _>(request.params, |p| {
+        git::git_list_branches(p.repo_path, p.kind, p.pattern)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
     "git_create_branch" => {
       parse_and_execute::<CreateBranchParam, _>(request.params, |p| {
         git::git_create_branch(p.repo_path, p.branch, p.start_point)
@@ -235,8 +682,8 @@ fn handle_request(request: RpcRequest) -> RpcResponse<serde_json::Value> {
       })
     }
     "git_list_worktrees" => {
-      parse_and_execute::<RepoPathParam, _>(request.params, |p| {
-        git::git_list_worktrees(p.repo_path)
+      parse_and_execute::<ListWorktreesParam, _>(request.params, |p| {
+        git::git_list_worktrees(p.repo_path, p.include_status)
           .map(|value| serde_json::to_value(value).unwrap_or_default())
       })
     }
@@ -247,6 +694,7 @@ fn handle_request(request: RpcRequest) -> RpcResponse<serde_json::Value> {
           p.path,
           p.branch,
           p.create_branch.unwrap_or(false),
+          p.options,
         )
         .map(|_| serde_json::Value::Null)
       })
@@ -257,12 +705,102 @@ fn handle_request(request: RpcRequest) -> RpcResponse<serde_json::Value> {
           .map(|_| serde_json::Value::Null)
       })
     }
+    "git_lock_worktree" => {
+      parse_and_execute::<LockWorktreeParam, _>(request.params, |p| {
+        git::git_lock_worktree(p.worktree_path, p.reason)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_unlock_worktree" => {
+      parse_and_execute::<WorktreePathParam, _>(request.params, |p| {
+        git::git_unlock_worktree(p.worktree_path)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_move_worktree" => {
+      parse_and_execute::<MoveWorktreeParam, _>(request.params, |p| {
+        git::git_move_worktree(p.worktree_path, p.new_path, p.force)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_repair_worktrees" => {
+      parse_and_execute::<RepairWorktreesParam, _>(request.params, |p| {
+        git::git_repair_worktrees(p.repo_path, p.paths)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_prune_worktrees" => {
+      parse_and_execute::<PruneWorktreesParam, _>(request.params, |p| {
+        git::git_prune_worktrees(p.repo_path, p.dry_run)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_sparse_checkout_list" => {
+      parse_and_execute::<WorktreePathParam, _>(request.params, |p| {
+        git::git_sparse_checkout_list(p.worktree_path)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_sparse_checkout_set" => {
+      parse_and_execute::<SparseCheckoutParam, _>(request.params, |p| {
+        git::git_sparse_checkout_set(p.worktree_path, p.paths)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_sparse_checkout_add" => {
+      parse_and_execute::<SparseCheckoutParam, _>(request.params, |p| {
+        git::git_sparse_checkout_add(p.worktree_path, p.paths)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_sparse_checkout_reapply" => {
+      parse_and_execute::<WorktreePathParam, _>(request.params, |p| {
+        git::git_sparse_checkout_reapply(p.worktree_path)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_sparse_checkout_disable" => {
+      parse_and_execute::<WorktreePathParam, _>(request.params, |p| {
+        git::git_sparse_checkout_disable(p.worktree_path)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
     "git_delete_branch" => {
       parse_and_execute::<DeleteBranchParam, _>(request.params, |p| {
         git::git_delete_branch(p.repo_path, p.branch, p.force)
           .map(|_| serde_json::Value::Null)
       })
     }
+    "git_rename_branch" => {
+      parse_and_execute::<RenameBranchParam, _>(request.params, |p| {
+        git::git_rename_branch(p.repo_path, p.branch, p.new_name, p.force)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_set_branch_target" => {
+      parse_and_execute::<SetBranchTargetParam, _>(request.params, |p| {
+        git::git_set_branch_target(p.repo_path, p.branch, p.target, p.expected_oid, p.force)
+          .map(serde_json::Value::String)
+      })
+    }
+    "git_set_upstream" => {
+      parse_and_execute::<SetUpstreamParam, _>(request.params, |p| {
+        git::git_set_upstream(p.repo_path, p.branch, p.upstream)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_compare_refs" => {
+      parse_and_execute::<CompareRefsParam, _>(request.params, |p| {
+        git::git_compare_refs(p.repo_path, p.base, p.head)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_compare_refs_batch" => {
+      parse_and_execute::<CompareRefsBatchParam, _>(request.params, |p| {
+        git::git_compare_refs_batch(p.repo_path, p.base, p.heads)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
     "git_current_commit" => {
       parse_and_execute::<RepoPathParam, _>(request.params, |p| {
         git::git_current_commit(p.repo_path)
@@ -276,8 +814,14 @@ fn handle_request(request: RpcRequest) -> RpcResponse<serde_json::Value> {
       })
     }
     "git_status" => {
-      parse_and_execute::<RepoPathParam, _>(request.params, |p| {
-        git::git_status(p.repo_path)
+      parse_and_execute::<StatusParam, _>(request.params, |p| {
+        git::git_status(p.repo_path, p.include_ignored)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_status_summary" => {
+      parse_and_execute::<StatusParam, _>(request.params, |p| {
+        git::git_status_summary(p.repo_path, p.include_ignored)
           .map(|value| serde_json::to_value(value).unwrap_or_default())
       })
     }
@@ -287,6 +831,195 @@ fn handle_request(request: RpcRequest) -> RpcResponse<serde_json::Value> {
           .map(|value| serde_json::to_value(value).unwrap_or_default())
       })
     }
+    "git_stage" => {
+      parse_and_execute::<PathsParam, _>(request.params, |p| {
+        git::git_stage(p.repo_path, p.paths)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_unstage" => {
+      parse_and_execute::<PathsParam, _>(request.params, |p| {
+        git::git_unstage(p.repo_path, p.paths)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_commit" => {
+      parse_and_execute::<CommitParam, _>(request.params, |p| {
+        git::git_commit(
+          p.repo_path,
+          p.message,
+          p.author,
+          p.committer,
+          p.allow_empty,
+          p.amend,
+        )
+        .map(serde_json::Value::String)
+      })
+    }
+    "git_diff" => {
+      parse_and_execute::<GitDiffParam, _>(request.params, |p| {
+        git::git_diff(
+          p.repo_path,
+          p.mode,
+          p.from,
+          p.to,
+          p.paths,
+          p.context_lines,
+        )
+        .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_log" => {
+      parse_and_execute::<LogParam, _>(request.params, |p| {
+        git::git_log(
+          p.repo_path,
+          p.rev,
+          p.paths,
+          p.author,
+          p.limit,
+          p.offset,
+          p.first_parent,
+        )
+        .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_merge" => {
+      parse_and_execute::<MergeParam, _>(request.params, |p| {
+        git::git_merge(p.repo_path, p.branch, p.mode, p.message, p.dry_run)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_cherry_pick" => {
+      parse_and_execute::<CherryPickParam, _>(request.params, |p| {
+        git::git_cherry_pick(p.repo_path, p.commits, p.mainline, p.action)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_rebase" => {
+      parse_and_execute::<RebaseParam, _>(request.params, |p| {
+        git::git_rebase(p.repo_path, p.action, p.upstream, p.onto, p.branch)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_conflicts" => {
+      parse_and_execute::<ConflictsParam, _>(request.params, |p| {
+        git::git_conflicts(p.repo_path, p.paths, p.merged)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_resolve_conflict" => {
+      parse_and_execute::<ResolveConflictParam, _>(request.params, |p| {
+        git::git_resolve_conflict(p.repo_path, p.path, p.resolution, p.content)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_stash_push" => {
+      parse_and_execute::<StashPushParam, _>(request.params, |p| {
+        git::git_stash_push(
+          p.repo_path,
+          p.message,
+          p.include_untracked,
+          p.keep_index,
+          p.paths,
+        )
+        .map(serde_json::Value::String)
+      })
+    }
+    "git_stash_list" => {
+      parse_and_execute::<RepoPathParam, _>(request.params, |p| {
+        git::git_stash_list(p.repo_path)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_stash_apply" => {
+      parse_and_execute::<StashApplyParam, _>(request.params, |p| {
+        git::git_stash_apply(p.repo_path, p.index, p.reinstate_index)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_stash_pop" => {
+      parse_and_execute::<StashApplyParam, _>(request.params, |p| {
+        git::git_stash_pop(p.repo_path, p.index, p.reinstate_index)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_stash_drop" => {
+      parse_and_execute::<StashDropParam, _>(request.params, |p| {
+        git::git_stash_drop(p.repo_path, p.index).map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_show_file" => {
+      parse_and_execute::<ShowFileParam, _>(request.params, |p| {
+        git::git_show_file(p.repo_path, p.rev, p.path)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_list_tree" => {
+      parse_and_execute::<ListTreeParam, _>(request.params, |p| {
+        git::git_list_tree(p.repo_path, p.rev, p.path)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_checkpoint_create" => {
+      parse_and_execute::<CheckpointCreateParam, _>(request.params, |p| {
+        checkpoint::git_checkpoint_create(p.repo_path, p.attempt, p.turn, p.message)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_checkpoint_list" => {
+      parse_and_execute::<CheckpointListParam, _>(request.params, |p| {
+        checkpoint::git_checkpoint_list(p.repo_path, p.attempt)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_checkpoint_diff" => {
+      parse_and_execute::<CheckpointDiffParam, _>(request.params, |p| {
+        checkpoint::git_checkpoint_diff(p.repo_path, p.attempt, p.from, p.to, p.context_lines)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_checkpoint_restore" => {
+      parse_and_execute::<CheckpointParam, _>(request.params, |p| {
+        checkpoint::git_checkpoint_restore(p.repo_path, p.attempt, p.turn)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_checkpoint_delete" => {
+      parse_and_execute::<CheckpointDeleteParam, _>(request.params, |p| {
+        checkpoint::git_checkpoint_delete(p.repo_path, p.attempt, p.turn)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
+    "git_pool_configure" => {
+      parse_and_execute::<PoolConfigureParam, _>(request.params, |p| {
+        pool::git_pool_configure(p.repo_path, p.size)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_pool_status" => {
+      parse_and_execute::<RepoPathParam, _>(request.params, |p| {
+        pool::git_pool_status(p.repo_path)
+          .map(|value| serde_json::to_value(value).unwrap_or_default())
+      })
+    }
+    "git_pool_acquire" => {
+      parse_and_execute::<CreateWorktreeParam, _>(request.params, |p| {
+        pool::git_pool_acquire(
+          p.repo_path,
+          p.path,
+          p.branch,
+          p.create_branch.unwrap_or(false),
+          p.options,
+        )
+        .map(serde_json::Value::Bool)
+      })
+    }
+    "git_pool_drain" => {
+      parse_and_execute::<RepoPathParam, _>(request.params, |p| {
+        pool::git_pool_drain(p.repo_path)
+          .map(|_| serde_json::Value::Null)
+      })
+    }
     "diff_unified" => {
       parse_and_execute::<DiffRequest, _>(request.params, |p| {
         let context = p.context_lines.unwrap_or(3).min(200);
@@ -294,12 +1027,28 @@ fn handle_request(request: RpcRequest) -> RpcResponse<serde_json::Value> {
           &p.original,
           &p.modified,
           context,
+          &p.options,
         )))
       })
     }
-    _ => Err("unknown_method".to_string()),
+    "diff_structured" => {
+      parse_and_execute::<StructuredDiffRequest, _>(request.params, |p| {
+        let context = p.context_lines.unwrap_or(3).min(200);
+        let hunks =
+          diff::structured_diff(&p.original, &p.modified, context, &p.options, p.inline);
+        Ok(serde_json::to_value(hunks).unwrap_or_default())
+      })
+    }
+    _ => Err(GitError::new(GitErrorCode::UnknownMethod, "unknown_method")),
   };
 
+  to_response(id, result)
+}
+
+fn to_response(
+  id: String,
+  result: Result<serde_json::Value, GitError>,
+) -> RpcResponse<serde_json::Value> {
   match result {
     Ok(value) => RpcResponse {
       id,
@@ -319,11 +1068,12 @@ fn handle_request(request: RpcRequest) -> RpcResponse<serde_json::Value> {
 fn parse_and_execute<P, F>(
   params: serde_json::Value,
   handler: F,
-) -> Result<serde_json::Value, String>
+) -> Result<serde_json::Value, GitError>
 where
   P: for<'de> Deserialize<'de>,
-  F: FnOnce(P) -> Result<serde_json::Value, String>,
+  F: FnOnce(P) -> Result<serde_json::Value, GitError>,
 {
-  let parsed: P = serde_json::from_value(params).map_err(|_| "invalid_params".to_string())?;
+  let parsed: P = serde_json::from_value(params)
+    .map_err(|_| GitError::new(GitErrorCode::InvalidParams, "invalid_params"))?;
   handler(parsed)
 }
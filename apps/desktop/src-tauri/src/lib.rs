@@ -10,6 +10,8 @@ use std::process::{Command, Stdio};
 use tauri::{AppHandle, Manager, RunEvent};
 use git::{
   git_branch_exists,
+  git_compare_refs,
+  git_compare_refs_batch,
   git_create_branch,
   git_create_worktree,
   git_current_branch,
@@ -17,18 +19,55 @@ use git::{
   git_default_branch,
   git_delete_branch,
   git_is_repo,
+  git_list_branches,
   git_list_worktrees,
+  git_lock_worktree,
+  git_move_worktree,
+  git_prune_worktrees,
   git_remove_worktree,
+  git_repair_worktrees,
+  git_rename_branch,
   git_repo_root,
   git_reset_hard,
+  git_set_branch_target,
+  git_set_upstream,
+  git_sparse_checkout_add,
+  git_sparse_checkout_disable,
+  git_sparse_checkout_list,
+  git_sparse_checkout_reapply,
+  git_sparse_checkout_set,
   git_status,
+  git_status_summary,
+  git_unlock_worktree,
   git_changed_files,
+  git_stage,
+  git_unstage,
+  git_commit,
+  git_diff,
+  git_log,
+  git_merge,
+  git_cherry_pick,
+  git_rebase,
+  git_conflicts,
+  git_resolve_conflict,
+  git_stash_push,
+  git_stash_list,
+  git_stash_apply,
+  git_stash_pop,
+  git_stash_drop,
+  git_show_file,
+  git_list_tree,
 };
 
+mod checkpoint;
+mod deps;
 mod diff;
 mod git;
+mod pool;
 mod watch;
 mod git_rpc;
+#[cfg(test)]
+mod test_util;
 
 const AUTH_FILE_NAME: &str = "forksd.auth";
 const DEFAULT_BIND: &str = "127.0.0.1";
@@ -51,9 +90,24 @@ fn compute_unified_diff(
   original: String,
   modified: String,
   context_lines: Option<usize>,
+  options: Option<diff::DiffOptions>,
 ) -> Result<String, String> {
   let context = context_lines.unwrap_or(3).min(200);
-  Ok(diff::unified_diff(&original, &modified, context))
+  let options = options.unwrap_or_default();
+  Ok(diff::unified_diff(&original, &modified, context, &options))
+}
+
+#[tauri::command]
+fn compute_structured_diff(
+  original: String,
+  modified: String,
+  context_lines: Option<usize>,
+  options: Option<diff::DiffOptions>,
+  inline: Option<diff::InlineGranularity>,
+) -> Result<Vec<diff::DiffHunk>, String> {
+  let context = context_lines.unwrap_or(3).min(200);
+  let options = options.unwrap_or_default();
+  Ok(diff::structured_diff(&original, &modified, context, &options, inline))
 }
 
 fn forksd_port() -> u16 {
@@ -258,20 +312,65 @@ pub fn run() {
     })
     .invoke_handler(tauri::generate_handler![
       compute_unified_diff,
+      compute_structured_diff,
       git_is_repo,
       git_repo_root,
       git_default_branch,
       git_current_branch,
       git_branch_exists,
+      git_list_branches,
       git_create_branch,
       git_list_worktrees,
       git_create_worktree,
       git_remove_worktree,
+      git_lock_worktree,
+      git_unlock_worktree,
+      git_move_worktree,
+      git_repair_worktrees,
+      git_prune_worktrees,
+      git_sparse_checkout_list,
+      git_sparse_checkout_set,
+      git_sparse_checkout_add,
+      git_sparse_checkout_reapply,
+      git_sparse_checkout_disable,
       git_delete_branch,
+      git_rename_branch,
+      git_set_branch_target,
+      git_set_upstream,
+      git_compare_refs,
+      git_compare_refs_batch,
       git_current_commit,
       git_reset_hard,
       git_status,
+      git_status_summary,
       git_changed_files,
+      git_stage,
+      git_unstage,
+      git_commit,
+      git_diff,
+      git_log,
+      git_merge,
+      git_cherry_pick,
+      git_rebase,
+      git_conflicts,
+      git_resolve_conflict,
+      git_stash_push,
+      git_stash_list,
+      git_stash_apply,
+      git_stash_pop,
+      git_stash_drop,
+      git_show_file,
+      git_list_tree,
+      checkpoint::git_checkpoint_create,
+      checkpoint::git_checkpoint_list,
+      checkpoint::git_checkpoint_diff,
+      checkpoint::git_checkpoint_restore,
+      checkpoint::git_checkpoint_delete,
+      pool::git_pool_configure,
+      pool::git_pool_status,
+      pool::git_pool_acquire,
+      pool::git_pool_drain,
+      deps::git_clone_deps,
       forksd_connection_info,
       forksd_rotate_token,
       watch::watch_add,
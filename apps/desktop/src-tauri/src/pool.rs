@@ -0,0 +1,402 @@
+use git2::build::CheckoutBuilder;
+use git2::{BranchType, ObjectType, Oid, Repository, Worktree, WorktreeLockStatus};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+
+use crate::git::{
+  ensure_not_checked_out,
+  git_create_worktree,
+  git_move_worktree,
+  git_remove_worktree,
+  open_repo_at,
+  prepare_worktree_head,
+  GitError,
+  GitErrorCode,
+  GitWorktreeOptions,
+  WorktreeHead,
+};
+
+// Pooled worktrees stay locked with this reason: it keeps prune from removing
+// them and tells them apart from worktrees that merely live under the pool dir.
+const POOL_LOCK_REASON: &str = "forks worktree pool";
+const MAX_POOL_SIZE: usize = 16;
+
+#[derive(Default)]
+struct PoolState {
+  size: usize,
+  replenishing: bool,
+}
+
+/// Pool settings per repository, keyed by its canonical common git dir.
+static POOLS: OnceLock<Mutex<HashMap<PathBuf, PoolState>>> = OnceLock::new();
+
+fn lock_pools() -> Result<MutexGuard<'static, HashMap<PathBuf, PoolState>>, GitError> {
+  POOLS
+    .get_or_init(|| Mutex::new(HashMap::new()))
+    .lock()
+    .map_err(|_| GitError::new(GitErrorCode::Other, "worktree pool lock poisoned"))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorktreePoolStatus {
+  /// Directory the pooled worktrees are checked out under.
+  pub dir: String,
+  pub size: usize,
+  pub available: usize,
+  pub replenishing: bool,
+}
+
+/// Returns the pool key and directory for `repo`:
+/// `~/.forks/pool/<hash of the common git dir>`.
+fn pool_location(repo: &Repository) -> Result<(PathBuf, PathBuf), GitError> {
+  let home = std::env::var_os("HOME")
+    .ok_or_else(|| GitError::new(GitErrorCode::Other, "HOME is not set"))?;
+  let key = std::fs::canonicalize(repo.commondir())?;
+  let hash = Oid::hash_object(ObjectType::Blob, key.to_string_lossy().as_bytes())?;
+  let dir = PathBuf::from(home)
+    .join(".forks")
+    .join("pool")
+    .join(&hash.to_string()[..12]);
+  Ok((key, dir))
+}
+
+/// Pooled worktrees ready to be handed out, by path.
+fn free_slots(repo: &Repository, dir: &Path) -> Result<Vec<PathBuf>, GitError> {
+  let mut slots = Vec::new();
+  let names = repo.worktrees()?;
+  for name in names.iter().flatten() {
+    let worktree = repo.find_worktree(name)?;
+    let pooled = matches!(
+      worktree.is_locked(),
+      Ok(WorktreeLockStatus::Locked(Some(ref reason))) if reason == POOL_LOCK_REASON
+    );
+    if pooled && worktree.path().starts_with(dir) && worktree.path().is_dir() {
+      slots.push(worktree.path().to_path_buf());
+    }
+  }
+  slots.sort();
+  Ok(slots)
+}
+
+/// Checks out one more detached worktree at HEAD under `dir`.
+fn add_slot(repo_path: &str, repo: &Repository, dir: &Path) -> Result<(), GitError> {
+  std::fs::create_dir_all(dir)?;
+  let admin_root = repo.commondir().join("worktrees");
+  let name = (0..)
+    .map(|n| format!("forks-pool-{}", n))
+    .find(|name| !dir.join(name).exists() && !admin_root.join(name).exists())
+    .unwrap_or_default();
+  git_create_worktree(
+    repo_path.to_string(),
+    dir.join(name).to_string_lossy().into_owned(),
+    None,
+    false,
+    Some(GitWorktreeOptions {
+      detached: true,
+      lock_reason: Some(POOL_LOCK_REASON.to_string()),
+      ..Default::default()
+    }),
+  )
+}
+
+fn fill_pool(repo_path: &str, key: &Path) -> Result<(), GitError> {
+  loop {
+    let size = lock_pools()?.get(key).map(|state| state.size).unwrap_or(0);
+    let repo = open_repo_at(repo_path)?;
+    let (_, dir) = pool_location(&repo)?;
+    if free_slots(&repo, &dir)?.len() >= size {
+      return Ok(());
+    }
+    add_slot(repo_path, &repo, &dir)?;
+  }
+}
+
+/// Tops the pool up on a background thread, unless a refill is already running.
+fn schedule_replenish(repo_path: &str, key: &Path) -> Result<(), GitError> {
+  {
+    let mut pools = lock_pools()?;
+    let state = pools.entry(key.to_path_buf()).or_default();
+    if state.replenishing || state.size == 0 {
+      return Ok(());
+    }
+    state.replenishing = true;
+  }
+  let repo_path = repo_path.to_string();
+  let key = key.to_path_buf();
+  std::thread::spawn(move || {
+    if let Err(err) = fill_pool(&repo_path, &key) {
+      eprintln!("[worktree-pool] replenish failed: {}", err);
+    }
+    if let Ok(mut pools) = lock_pools() {
+      if let Some(state) = pools.get_mut(&key) {
+        state.replenishing = false;
+      }
+    }
+  });
+  Ok(())
+}
+
+fn pool_status(repo: &Repository) -> Result<WorktreePoolStatus, GitError> {
+  let (key, dir) = pool_location(repo)?;
+  let available = free_slots(repo, &dir)?.len();
+  let pools = lock_pools()?;
+  let state = pools.get(&key);
+  Ok(WorktreePoolStatus {
+    dir: dir.to_string_lossy().into_owned(),
+    size: state.map(|value| value.size).unwrap_or(0),
+    available,
+    replenishing: state.map(|value| value.replenishing).unwrap_or(false),
+  })
+}
+
+/// Keeps `size` pre-checked-out worktrees ready for `repo_path` (at most 16).
+/// Missing ones are created in the background; surplus ones are removed.
+#[tauri::command]
+pub fn git_pool_configure(
+  repo_path: String,
+  size: usize,
+) -> Result<WorktreePoolStatus, GitError> {
+  let repo = open_repo_at(&repo_path)?;
+  let (key, dir) = pool_location(&repo)?;
+  let size = size.min(MAX_POOL_SIZE);
+  {
+    // Held while removing so a concurrent acquire can't take a surplus slot.
+    let mut pools = lock_pools()?;
+    pools.entry(key.clone()).or_default().size = size;
+    for slot in free_slots(&repo, &dir)?.into_iter().skip(size) {
+      git_remove_worktree(slot.to_string_lossy().into_owned(), Some(true))?;
+    }
+  }
+  schedule_replenish(&repo_path, &key)?;
+  pool_status(&repo)
+}
+
+#[tauri::command]
+pub fn git_pool_status(repo_path: String) -> Result<WorktreePoolStatus, GitError> {
+  let repo = open_repo_at(&repo_path)?;
+  pool_status(&repo)
+}
+
+/// Switches a slot just moved to `path` over to `head` and applies the caller's
+/// lock. Only files that differ from the slot's checkout are rewritten.
+fn switch_slot(
+  repo: &Repository,
+  path: &str,
+  head: &WorktreeHead,
+  options: &GitWorktreeOptions,
+) -> Result<(), GitError> {
+  let worktree_repo = Repository::open(path)?;
+  let worktree = Worktree::open_from_repository(&worktree_repo)?;
+  worktree.unlock()?;
+  let oid = match head {
+    WorktreeHead::Branch(ref_name) => repo.refname_to_id(ref_name)?,
+    WorktreeHead::Detached(oid) => *oid,
+  };
+  let commit = worktree_repo.find_commit(oid)?;
+  let mut checkout = CheckoutBuilder::new();
+  checkout.force();
+  worktree_repo.checkout_tree(commit.as_object(), Some(&mut checkout))?;
+  match head {
+    WorktreeHead::Branch(ref_name) => worktree_repo.set_head(ref_name)?,
+    WorktreeHead::Detached(oid) => worktree_repo.set_head_detached(*oid)?,
+  }
+  if options.lock || options.lock_reason.is_some() {
+    worktree.lock(options.lock_reason.as_deref())?;
+  }
+  Ok(())
+}
+
+/// Does the work of `git_pool_acquire` with slots taken from `dir`. Callers
+/// hold the pools lock throughout, so a slot can't be handed out twice and a
+/// branch can't be checked out by two callers at once. On failure nothing is
+/// left behind: a moved slot is removed and a branch created here is deleted.
+fn acquire(
+  repo_path: &str,
+  repo: &Repository,
+  dir: &Path,
+  path: &str,
+  branch: Option<String>,
+  create_branch: bool,
+  options: GitWorktreeOptions,
+) -> Result<bool, GitError> {
+  let head = prepare_worktree_head(repo, branch.as_deref(), create_branch, &options)?;
+  let created = if create_branch { branch.clone() } else { None };
+  let result = acquire_head(repo_path, repo, dir, path, branch, head, options);
+  if let (Err(_), Some(name)) = (&result, created) {
+    let deleted = repo
+      .find_branch(&name, BranchType::Local)
+      .and_then(|mut value| value.delete());
+    if let Err(err) = deleted {
+      eprintln!("[worktree-pool] could not delete branch {}: {}", name, err);
+    }
+  }
+  result
+}
+
+fn acquire_head(
+  repo_path: &str,
+  repo: &Repository,
+  dir: &Path,
+  path: &str,
+  branch: Option<String>,
+  head: WorktreeHead,
+  options: GitWorktreeOptions,
+) -> Result<bool, GitError> {
+  if let WorktreeHead::Branch(ref ref_name) = head {
+    ensure_not_checked_out(repo, ref_name)?;
+  }
+  if let Some(slot) = free_slots(repo, dir)?.into_iter().next() {
+    match git_move_worktree(slot.to_string_lossy().into_owned(), path.to_string(), Some(true)) {
+      Ok(()) => {
+        if let Err(err) = switch_slot(repo, path, &head, &options) {
+          // Half switched and no longer in the pool, so it can only go.
+          if let Err(cleanup) = git_remove_worktree(path.to_string(), Some(true)) {
+            eprintln!("[worktree-pool] could not remove {}: {}", path, cleanup);
+          }
+          return Err(err);
+        }
+        return Ok(true);
+      }
+      // The slot is still in place, so nothing was moved and a fresh
+      // worktree can be created instead.
+      Err(err) if slot.is_dir() => {
+        eprintln!("[worktree-pool] could not move {}: {}", slot.display(), err);
+      }
+      Err(err) => return Err(err),
+    }
+  }
+  // HEAD is resolved and any new branch exists by now, so the fallback must
+  // neither create the branch again nor re-resolve the start point.
+  let (branch, options) = match head {
+    WorktreeHead::Branch(_) => (
+      branch,
+      GitWorktreeOptions {
+        start_point: None,
+        ..options
+      },
+    ),
+    WorktreeHead::Detached(oid) => (
+      None,
+      GitWorktreeOptions {
+        start_point: Some(oid.to_string()),
+        ..options
+      },
+    ),
+  };
+  git_create_worktree(repo_path.to_string(), path.to_string(), branch, false, Some(options))?;
+  Ok(false)
+}
+
+/// Creates a worktree like `git_create_worktree`, but takes a pooled one when
+/// available: it is moved to `path` and switched to the requested HEAD, which
+/// only rewrites files that differ. Falls back to a fresh worktree when the
+/// pool is empty or the slot can't be moved, e.g. across filesystems. Returns
+/// whether a pooled worktree was used.
+#[tauri::command]
+pub fn git_pool_acquire(
+  repo_path: String,
+  path: String,
+  branch: Option<String>,
+  create_branch: bool,
+  options: Option<GitWorktreeOptions>,
+) -> Result<bool, GitError> {
+  let options = options.unwrap_or_default();
+  // An empty or sparse worktree gains nothing from a fully populated slot.
+  if options.no_checkout || options.sparse_paths.is_some() {
+    git_create_worktree(repo_path, path, branch, create_branch, Some(options))?;
+    return Ok(false);
+  }
+  let repo = open_repo_at(&repo_path)?;
+  let (key, dir) = pool_location(&repo)?;
+  let moved = {
+    let _pools = lock_pools()?;
+    acquire(&repo_path, &repo, &dir, &path, branch, create_branch, options)?
+  };
+  schedule_replenish(&repo_path, &key)?;
+  Ok(moved)
+}
+
+/// Removes every pooled worktree of `repo_path` and stops replenishing.
+#[tauri::command]
+pub fn git_pool_drain(repo_path: String) -> Result<(), GitError> {
+  let repo = open_repo_at(&repo_path)?;
+  let (key, dir) = pool_location(&repo)?;
+  // Held while removing so a concurrent acquire can't take a slot being removed.
+  let mut pools = lock_pools()?;
+  if let Some(state) = pools.get_mut(&key) {
+    state.size = 0;
+  }
+  for slot in free_slots(&repo, &dir)? {
+    git_remove_worktree(slot.to_string_lossy().into_owned(), Some(true))?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::TestRepo;
+
+  fn pooled_repo() -> (TestRepo, PathBuf) {
+    let test = TestRepo::new();
+    test.write("a.txt", "a\n");
+    test.commit("first");
+    let dir = test.scratch("pool");
+    add_slot(&test.path(), &test.repo, &dir).unwrap();
+    (test, dir)
+  }
+
+  fn worktree_names(repo: &Repository) -> Vec<String> {
+    let names = repo.worktrees().unwrap();
+    names.iter().flatten().map(str::to_string).collect()
+  }
+
+  #[test]
+  fn acquire_moves_a_slot_onto_the_branch() {
+    let (test, dir) = pooled_repo();
+    assert_eq!(worktree_names(&test.repo), vec!["forks-pool-0".to_string()]);
+    let path = test.scratch("work").to_string_lossy().into_owned();
+    let options = GitWorktreeOptions::default();
+    let moved =
+      acquire(&test.path(), &test.repo, &dir, &path, Some("feature".into()), true, options)
+        .unwrap();
+    assert!(moved);
+    assert_eq!(worktree_names(&test.repo), vec!["work".to_string()]);
+    assert!(free_slots(&test.repo, &dir).unwrap().is_empty());
+    let worktree_repo = Repository::open(&path).unwrap();
+    assert_eq!(worktree_repo.head().unwrap().shorthand(), Some("feature"));
+    assert_eq!(std::fs::read_to_string(Path::new(&path).join("a.txt")).unwrap(), "a\n");
+    let worktree = Worktree::open_from_repository(&worktree_repo).unwrap();
+    assert!(matches!(worktree.is_locked().unwrap(), WorktreeLockStatus::Unlocked));
+  }
+
+  #[test]
+  fn acquire_removes_the_slot_when_switching_fails() {
+    let (test, dir) = pooled_repo();
+    let path = test.scratch("work").to_string_lossy().into_owned();
+    let head = WorktreeHead::Branch("refs/heads/missing".into());
+    let options = GitWorktreeOptions::default();
+    assert!(acquire_head(&test.path(), &test.repo, &dir, &path, None, head, options).is_err());
+    assert!(!Path::new(&path).exists());
+    assert!(worktree_names(&test.repo).is_empty());
+  }
+
+  #[test]
+  fn acquire_deletes_the_branch_it_created_on_failure() {
+    let (test, dir) = pooled_repo();
+    let path = test.scratch("work");
+    std::fs::create_dir_all(&path).unwrap();
+    std::fs::write(path.join("taken.txt"), "taken\n").unwrap();
+    let path = path.to_string_lossy().into_owned();
+    let options = GitWorktreeOptions::default();
+    let result =
+      acquire(&test.path(), &test.repo, &dir, &path, Some("feature".into()), true, options);
+    assert!(result.is_err());
+    assert!(test.repo.find_branch("feature", BranchType::Local).is_err());
+    assert_eq!(free_slots(&test.repo, &dir).unwrap().len(), 1);
+    assert!(Path::new(&path).join("taken.txt").exists());
+  }
+}
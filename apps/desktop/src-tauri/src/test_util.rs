@@ -0,0 +1,103 @@
+use git2::{IndexAddOption, Oid, Repository, Signature};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Fresh directory under the system temp dir, removed on drop.
+pub struct TempDir {
+  path: PathBuf,
+}
+
+impl TempDir {
+  pub fn new() -> Self {
+    let name = format!(
+      "forks-test-{}-{}",
+      std::process::id(),
+      NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    );
+    let path = std::env::temp_dir().join(name);
+    let _ = fs::remove_dir_all(&path);
+    fs::create_dir_all(&path).unwrap();
+    Self { path }
+  }
+
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+}
+
+impl Drop for TempDir {
+  fn drop(&mut self) {
+    let _ = fs::remove_dir_all(&self.path);
+  }
+}
+
+/// Repository on `main` with a local identity, in its own temp dir.
+pub struct TestRepo {
+  pub repo: Repository,
+  dir: TempDir,
+}
+
+impl TestRepo {
+  pub fn new() -> Self {
+    let dir = TempDir::new();
+    let root = dir.path().join("repo");
+    let repo = Repository::init(&root).unwrap();
+    repo.set_head("refs/heads/main").unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Test").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+    Self { repo, dir }
+  }
+
+  pub fn root(&self) -> &Path {
+    self.repo.workdir().unwrap()
+  }
+
+  pub fn path(&self) -> String {
+    self.root().to_string_lossy().into_owned()
+  }
+
+  /// Sibling directory for worktrees and other out-of-repo paths.
+  pub fn scratch(&self, name: &str) -> PathBuf {
+    self.dir.path().join(name)
+  }
+
+  pub fn write(&self, file: &str, content: &str) {
+    let path = self.root().join(file);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+  }
+
+  pub fn read(&self, file: &str) -> String {
+    fs::read_to_string(self.root().join(file)).unwrap()
+  }
+
+  /// Stages everything in the worktree and commits it on HEAD.
+  pub fn commit(&self, message: &str) -> Oid {
+    let mut index = self.repo.index().unwrap();
+    index.add_all(["*"], IndexAddOption::DEFAULT, None).unwrap();
+    index.update_all(["*"], None).unwrap();
+    index.write().unwrap();
+    let tree = self.repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = Signature::now("Test", "test@example.com").unwrap();
+    let parent = self.repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+    let parents: Vec<_> = parent.iter().collect();
+    self
+      .repo
+      .commit(Some("HEAD"), &signature, &signature, message, &tree, &parents)
+      .unwrap()
+  }
+
+  /// Points `branch` at `target` and checks it out.
+  pub fn checkout(&self, branch: &str, target: Oid) {
+    let ref_name = format!("refs/heads/{}", branch);
+    self.repo.reference(&ref_name, target, true, "test checkout").unwrap();
+    self.repo.set_head(&ref_name).unwrap();
+    let mut checkout = git2::build::CheckoutBuilder::new();
+    checkout.force();
+    self.repo.checkout_head(Some(&mut checkout)).unwrap();
+  }
+}
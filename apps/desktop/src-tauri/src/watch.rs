@@ -4,15 +4,17 @@ use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{self, Sender};
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tauri::{AppHandle, Emitter};
 
+use crate::git::{in_sparse_cone, sparse_cone_dirs};
+
 const DEFAULT_DEBOUNCE_MS: u64 = 150;
 const MIN_DEBOUNCE_MS: u64 = 50;
 const MAX_DEBOUNCE_MS: u64 = 2000;
-const EVENT_NAME: &str = "fs/watch";
+pub const EVENT_NAME: &str = "fs/watch";
 // Cap pending paths to prevent unbounded memory growth during burst events
 const MAX_PENDING_PATHS: usize = 10_000;
 const DEFAULT_IGNORED_DIRS: [&str; 12] = [
@@ -44,6 +46,9 @@ struct WatchRegistry {
 struct WatchEntry {
   #[allow(dead_code)]
   sender: Sender<Event>,
+  // Watches delivered to a callback belong to that subscriber (e.g. an RPC
+  // connection), which removes them itself; `remove_all` leaves them alone.
+  subscribed: bool,
   _watcher: RecommendedWatcher,
   _git_watchers: Vec<RecommendedWatcher>,
 }
@@ -66,7 +71,7 @@ pub struct WatchAddResponse {
 
 #[derive(Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
-struct WatchEventPayload {
+pub struct WatchEventPayload {
   watch_id: String,
   repo_root: String,
   worktree_path: String,
@@ -76,12 +81,23 @@ struct WatchEventPayload {
   timestamp_ms: u64,
 }
 
+/// Where a watch delivers its debounced event batches.
+#[derive(Clone)]
+pub enum WatchSink {
+  /// Emitted to the webview as `fs/watch` events.
+  App(AppHandle),
+  /// Passed to a subscriber callback, e.g. a git RPC connection.
+  Callback(Arc<dyn Fn(&WatchEventPayload) + Send + Sync>),
+}
+
 struct FilterConfig {
   #[allow(dead_code)]
   repo_root: PathBuf,
   worktree_path: PathBuf,
   git_dir: Option<PathBuf>,
   ignored_dirs: HashSet<String>,
+  // Cone directories of a sparse worktree; paths outside the cone are dropped.
+  sparse_dirs: Option<Vec<String>>,
 }
 
 struct WorkerConfig {
@@ -109,7 +125,7 @@ impl WatchManager {
 
   pub fn add_watch(
     &self,
-    app: AppHandle,
+    sink: WatchSink,
     request: WatchAddRequest,
   ) -> Result<WatchAddResponse, String> {
     let worktree_path = canonicalize_absolute(&request.path)?;
@@ -131,6 +147,11 @@ impl WatchManager {
       None
     };
 
+    // Sparse worktrees are watched as of now; later pattern changes aren't
+    // picked up until the watch is re-added.
+    let sparse_dirs = sparse_cone_dirs(&worktree_path);
+    let worktree_paths = worktree_watch_paths(&worktree_path, sparse_dirs.as_deref());
+
     let filter = FilterConfig {
       repo_root: repo_root.clone(),
       worktree_path: worktree_path.clone(),
@@ -139,6 +160,7 @@ impl WatchManager {
         .iter()
         .map(|value| value.to_string())
         .collect(),
+      sparse_dirs,
     };
 
     let watch_id = {
@@ -162,11 +184,14 @@ impl WatchManager {
       filter,
     };
 
-    let sender = spawn_worker(app, worker_config);
+    let subscribed = matches!(sink, WatchSink::Callback(_));
+    let sender = spawn_worker(sink, worker_config);
     let mut watcher = make_watcher(sender.clone())?;
-    watcher
-      .watch(&worktree_path, RecursiveMode::Recursive)
-      .map_err(|err| err.to_string())?;
+    for (path, recursive) in worktree_paths {
+      watcher
+        .watch(&path, recursive)
+        .map_err(|err| err.to_string())?;
+    }
 
     let mut git_watchers = Vec::new();
     if let Some(git_dir) = git_dir {
@@ -190,6 +215,7 @@ impl WatchManager {
       watch_id.clone(),
       WatchEntry {
         sender,
+        subscribed,
         _watcher: watcher,
         _git_watchers: git_watchers,
       },
@@ -209,7 +235,7 @@ impl WatchManager {
 
   pub fn remove_all(&self) -> Result<(), String> {
     let mut registry = self.inner.lock().map_err(|_| "watcher lock poisoned".to_string())?;
-    registry.entries.clear();
+    registry.entries.retain(|_, entry| entry.subscribed);
     Ok(())
   }
 }
@@ -220,7 +246,7 @@ pub fn watch_add(
   state: tauri::State<'_, WatchManager>,
   request: WatchAddRequest,
 ) -> Result<WatchAddResponse, String> {
-  state.add_watch(app, request)
+  state.add_watch(WatchSink::App(app), request)
 }
 
 #[tauri::command]
@@ -271,6 +297,47 @@ fn resolve_git_dir(repo_root: &Path) -> Option<PathBuf> {
   None
 }
 
+/// The whole worktree, or for a sparse one only its cone: cone directories
+/// recursively, plus the root and their ancestors for the files directly in them.
+/// Directories that don't exist yet are covered by watching their nearest
+/// existing ancestor recursively; `should_emit_path` drops what's outside the cone.
+fn worktree_watch_paths(
+  worktree_path: &Path,
+  sparse_dirs: Option<&[String]>,
+) -> Vec<(PathBuf, RecursiveMode)> {
+  let Some(dirs) = sparse_dirs else {
+    return vec![(worktree_path.to_path_buf(), RecursiveMode::Recursive)];
+  };
+  let mut wanted = vec![(worktree_path.to_path_buf(), RecursiveMode::NonRecursive)];
+  for dir in dirs {
+    for parent in Path::new(dir).ancestors().skip(1) {
+      if !parent.as_os_str().is_empty() {
+        wanted.push((worktree_path.join(parent), RecursiveMode::NonRecursive));
+      }
+    }
+    wanted.push((worktree_path.join(dir), RecursiveMode::Recursive));
+  }
+  let mut paths: Vec<(PathBuf, RecursiveMode)> = Vec::new();
+  for (path, mode) in wanted {
+    let (path, mode) = if path.is_dir() {
+      (path, mode)
+    } else {
+      match path.ancestors().skip(1).find(|parent| parent.is_dir()) {
+        Some(parent) if parent.starts_with(worktree_path) => {
+          (parent.to_path_buf(), RecursiveMode::Recursive)
+        }
+        _ => continue,
+      }
+    };
+    match paths.iter_mut().find(|(existing, _)| *existing == path) {
+      Some(entry) if mode == RecursiveMode::Recursive => entry.1 = mode,
+      Some(_) => {}
+      None => paths.push((path, mode)),
+    }
+  }
+  paths
+}
+
 fn git_watch_paths(git_dir: &Path) -> Vec<(PathBuf, RecursiveMode)> {
   vec![
     (git_dir.join("HEAD"), RecursiveMode::NonRecursive),
@@ -297,7 +364,7 @@ fn make_watcher(sender: Sender<Event>) -> Result<RecommendedWatcher, String> {
   .map_err(|err| err.to_string())
 }
 
-fn spawn_worker(app: AppHandle, config: WorkerConfig) -> Sender<Event> {
+fn spawn_worker(sink: WatchSink, config: WorkerConfig) -> Sender<Event> {
   let (sender, receiver) = mpsc::channel::<Event>();
   let thread_name = format!("watch-{}", config.watch_id);
 
@@ -321,11 +388,11 @@ fn spawn_worker(app: AppHandle, config: WorkerConfig) -> Sender<Event> {
             collect_event(&event, &config.filter, &mut pending_paths, &mut pending_kinds);
           }
           Err(mpsc::RecvTimeoutError::Timeout) => {
-            flush_events(&app, &config, &mut pending_paths, &mut pending_kinds);
+            flush_events(&sink, &config, &mut pending_paths, &mut pending_kinds);
             break;
           }
           Err(mpsc::RecvTimeoutError::Disconnected) => {
-            flush_events(&app, &config, &mut pending_paths, &mut pending_kinds);
+            flush_events(&sink, &config, &mut pending_paths, &mut pending_kinds);
             return;
           }
         }
@@ -372,7 +439,7 @@ fn collect_event(
 }
 
 fn flush_events(
-  app: &AppHandle,
+  sink: &WatchSink,
   config: &WorkerConfig,
   pending_paths: &mut HashSet<String>,
   pending_kinds: &mut HashSet<String>,
@@ -392,7 +459,12 @@ fn flush_events(
     timestamp_ms: now_ms(),
   };
 
-  let _ = app.emit(EVENT_NAME, payload);
+  match sink {
+    WatchSink::App(app) => {
+      let _ = app.emit(EVENT_NAME, payload);
+    }
+    WatchSink::Callback(callback) => callback(&payload),
+  }
 }
 
 fn should_emit_path(path: &Path, filter: &FilterConfig) -> bool {
@@ -409,6 +481,13 @@ fn should_emit_path(path: &Path, filter: &FilterConfig) -> bool {
     }
   }
 
+  if let Some(dirs) = &filter.sparse_dirs {
+    if let Ok(relative) = path.strip_prefix(&filter.worktree_path) {
+      let relative = relative.to_string_lossy();
+      return relative.is_empty() || in_sparse_cone(&relative, dirs);
+    }
+  }
+
   true
 }
 
@@ -458,3 +537,66 @@ fn now_ms() -> u64 {
     .unwrap_or_else(|_| Duration::from_millis(0))
     .as_millis() as u64
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::test_util::TempDir;
+
+  fn sparse_filter(root: &Path, dirs: &[&str]) -> FilterConfig {
+    FilterConfig {
+      repo_root: root.to_path_buf(),
+      worktree_path: root.to_path_buf(),
+      git_dir: None,
+      ignored_dirs: HashSet::new(),
+      sparse_dirs: Some(dirs.iter().map(|dir| dir.to_string()).collect()),
+    }
+  }
+
+  #[test]
+  fn watch_paths_cover_the_whole_worktree_without_a_cone() {
+    let dir = TempDir::new();
+    let paths = worktree_watch_paths(dir.path(), None);
+    assert_eq!(paths, vec![(dir.path().to_path_buf(), RecursiveMode::Recursive)]);
+  }
+
+  #[test]
+  fn watch_paths_cover_existing_cone_dirs() {
+    let dir = TempDir::new();
+    let root = dir.path();
+    fs::create_dir_all(root.join("apps/web")).unwrap();
+    let paths = worktree_watch_paths(root, Some(&["apps/web".to_string()]));
+    assert_eq!(
+      paths,
+      vec![
+        (root.to_path_buf(), RecursiveMode::NonRecursive),
+        (root.join("apps"), RecursiveMode::NonRecursive),
+        (root.join("apps/web"), RecursiveMode::Recursive),
+      ]
+    );
+  }
+
+  #[test]
+  fn watch_paths_fall_back_to_the_nearest_existing_ancestor() {
+    let dir = TempDir::new();
+    let root = dir.path();
+    fs::create_dir_all(root.join("apps")).unwrap();
+    let dirs = ["apps/web".to_string(), "docs/guide".to_string()];
+    let paths = worktree_watch_paths(root, Some(&dirs));
+    assert_eq!(
+      paths,
+      vec![
+        (root.to_path_buf(), RecursiveMode::Recursive),
+        (root.join("apps"), RecursiveMode::Recursive),
+      ]
+    );
+
+    // Events from the broader watch are still limited to the cone.
+    let filter = sparse_filter(root, &["apps/web", "docs/guide"]);
+    assert!(should_emit_path(&root.join("apps/web/index.ts"), &filter));
+    assert!(should_emit_path(&root.join("docs/guide/intro.md"), &filter));
+    assert!(should_emit_path(&root.join("README.md"), &filter));
+    assert!(!should_emit_path(&root.join("apps/api/main.rs"), &filter));
+    assert!(!should_emit_path(&root.join("packages/ui/button.tsx"), &filter));
+  }
+}
@@ -753,13 +753,24 @@ const handleAttemptPick: ToolHandler = async (data, store, _session) => {
   const chat = store.getChat(attempt.chatId);
   const workspace = chat ? store.getWorkspace(chat.workspaceId) : null;
 
+  let pickedMerged = false;
   if (workspace && attempt.branch) {
-    // Apply picked attempt's changes to workspace using git reset --hard
+    // Merge the picked attempt into the workspace; conflicts leave it untouched
     try {
-      const { resetHard, isValidGitRef } = await import("@forks-sh/git");
+      const { mergeBranch, isValidGitRef } = await import("@forks-sh/git");
       // Defense-in-depth: validate branch from DB before use in git command
       if (isValidGitRef(attempt.branch)) {
-        await resetHard(workspace.path, attempt.branch);
+        const result = await mergeBranch(workspace.path, attempt.branch, {
+          mode: "merge",
+        });
+        if (result.status === "conflicts") {
+          console.error(
+            "[MCP] Picked attempt branch conflicts with workspace:",
+            result.conflicts
+          );
+        } else {
+          pickedMerged = true;
+        }
       } else {
         console.error(
           "[MCP] Invalid branch name from database:",
@@ -768,7 +779,7 @@ const handleAttemptPick: ToolHandler = async (data, store, _session) => {
       }
     } catch (err) {
       console.error(
-        "[MCP] Failed to reset workspace to picked attempt branch:",
+        "[MCP] Failed to merge picked attempt branch into workspace:",
         err
       );
       // Don't fail the pick - the attempt is already marked as picked
@@ -781,12 +792,13 @@ const handleAttemptPick: ToolHandler = async (data, store, _session) => {
   // Re-fetch attempts for worktree cleanup (need current state after batch update)
   const allAttempts = store.listAttempts(attempt.chatId, 1000);
 
-  // Clean up ALL worktrees (including picked attempt) in background (non-blocking)
-  // The picked attempt's changes have been applied to main workspace via reset --hard,
-  // so its worktree is no longer needed. Non-picked attempts are also cleaned up.
+  // Clean up worktrees in background (non-blocking). Once merged, the picked
+  // attempt's worktree is no longer needed; if the merge didn't go through it is
+  // kept so its changes aren't lost. Non-picked attempts are always cleaned up.
   if (workspace) {
     const worktreesToCleanup = allAttempts
       .filter((a) => a.worktreePath && a.branch)
+      .filter((a) => pickedMerged || a.id !== attemptId)
       .map((a) => ({
         id: a.id,
         worktreePath: a.worktreePath as string,
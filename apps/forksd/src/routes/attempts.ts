@@ -116,13 +116,24 @@ export const createAttemptRoutes = (store: Store) => {
     const chat = store.getChat(picked.chatId);
     const workspace = chat ? store.getWorkspace(chat.workspaceId) : null;
 
-    // Apply picked attempt's changes to workspace using git reset --hard
+    // Merge the picked attempt into the workspace; conflicts leave it untouched
+    let pickedMerged = false;
     if (workspace && picked.branch) {
       try {
-        const { resetHard, isValidGitRef } = await import("@forks-sh/git");
+        const { mergeBranch, isValidGitRef } = await import("@forks-sh/git");
         // Defense-in-depth: validate branch from DB before use in git command
         if (isValidGitRef(picked.branch)) {
-          await resetHard(workspace.path, picked.branch);
+          const result = await mergeBranch(workspace.path, picked.branch, {
+            mode: "merge",
+          });
+          if (result.status === "conflicts") {
+            console.error(
+              "[routes/attempts] Picked attempt branch conflicts with workspace:",
+              result.conflicts
+            );
+          } else {
+            pickedMerged = true;
+          }
         } else {
           console.error(
             "[routes/attempts] Invalid branch name from database:",
@@ -131,7 +142,7 @@ export const createAttemptRoutes = (store: Store) => {
         }
       } catch (err) {
         console.error(
-          "[routes/attempts] Failed to reset workspace to picked attempt branch:",
+          "[routes/attempts] Failed to merge picked attempt branch into workspace:",
           err
         );
         // Don't fail the pick - the attempt is already marked as picked
@@ -144,12 +155,13 @@ export const createAttemptRoutes = (store: Store) => {
     // Re-fetch attempts for worktree cleanup (need current state after batch update)
     const allAttempts = store.listAttempts(picked.chatId, MAX_LIMIT, 0);
 
-    // Clean up ALL worktrees (including picked attempt) in background (non-blocking)
-    // The picked attempt's changes have been applied to main workspace via reset --hard,
-    // so its worktree is no longer needed. Non-picked attempts are also cleaned up.
+    // Clean up worktrees in background (non-blocking). Once merged, the picked
+    // attempt's worktree is no longer needed; if the merge didn't go through it is
+    // kept so its changes aren't lost. Non-picked attempts are always cleaned up.
     if (workspace) {
       const worktreesToCleanup = allAttempts
         .filter((a) => a.worktreePath && a.branch)
+        .filter((a) => pickedMerged || a.id !== picked.id)
         .map((a) => ({
           id: a.id,
           worktreePath: a.worktreePath as string,
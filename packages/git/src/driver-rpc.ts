@@ -1,8 +1,12 @@
 import type { GitDriver } from "./driver.js";
 import { requestRpc } from "./rpc-client.js";
 import type {
+  BranchInfo,
+  CloneDepsProgress,
+  CloneDepsResult,
   CreateWorktreeOpts,
   GitStatusEntry,
+  MergeResult,
   WorktreeInfo,
 } from "./types.js";
 
@@ -29,9 +33,17 @@ export const createRpcGitDriver = (socketPath: string): GitDriver => {
         startPoint,
       }),
 
-    listWorktrees: async (repoPath) =>
+    listBranches: async (repoPath, opts) =>
+      requestRpc<BranchInfo[]>(socketPath, "git_list_branches", {
+        repoPath,
+        kind: opts?.kind,
+        pattern: opts?.pattern,
+      }),
+
+    listWorktrees: async (repoPath, opts) =>
       requestRpc<WorktreeInfo[]>(socketPath, "git_list_worktrees", {
         repoPath,
+        includeStatus: opts?.includeStatus ?? false,
       }),
 
     createWorktree: async (repoPath, opts: CreateWorktreeOpts) =>
@@ -40,6 +52,14 @@ export const createRpcGitDriver = (socketPath: string): GitDriver => {
         path: opts.path,
         branch: opts.branch,
         createBranch: opts.createBranch ?? false,
+        options: {
+          startPoint: opts.startPoint,
+          detached: opts.detached ?? false,
+          noCheckout: opts.noCheckout ?? false,
+          lock: opts.lock ?? false,
+          lockReason: opts.lockReason,
+          sparsePaths: opts.sparsePaths,
+        },
       }),
 
     removeWorktree: async (worktreePath, opts) =>
@@ -48,6 +68,23 @@ export const createRpcGitDriver = (socketPath: string): GitDriver => {
         force: opts?.force ?? false,
       }),
 
+    cloneDeps: async (sourcePath, targetPath, paths, opts) =>
+      requestRpc<CloneDepsResult>(
+        socketPath,
+        "git_clone_deps",
+        {
+          sourcePath,
+          targetPath,
+          paths,
+          options: { allowHardlink: opts?.allowHardlink ?? false },
+        },
+        (method, params) => {
+          if (method === "git/clone_deps_progress") {
+            opts?.onProgress?.(params as CloneDepsProgress);
+          }
+        }
+      ),
+
     deleteBranch: async (repoPath, branch, force) =>
       requestRpc(socketPath, "git_delete_branch", {
         repoPath,
@@ -61,6 +98,15 @@ export const createRpcGitDriver = (socketPath: string): GitDriver => {
     resetHard: async (repoPath, ref) =>
       requestRpc(socketPath, "git_reset_hard", { repoPath, gitRef: ref }),
 
+    merge: async (repoPath, branch, opts) =>
+      requestRpc<MergeResult>(socketPath, "git_merge", {
+        repoPath,
+        branch,
+        mode: opts.mode,
+        message: opts.message,
+        dryRun: opts.dryRun ?? false,
+      }),
+
     getStatus: async (repoPath) =>
       requestRpc<GitStatusEntry[]>(socketPath, "git_status", { repoPath }),
 
@@ -1,7 +1,12 @@
 import type { GitDriver } from "./driver.js";
+import { toGitError } from "./errors.js";
 import type {
+  BranchInfo,
+  CloneDepsProgress,
+  CloneDepsResult,
   CreateWorktreeOpts,
   GitStatusEntry,
+  MergeResult,
   WorktreeInfo,
 } from "./types.js";
 
@@ -9,7 +14,15 @@ interface TauriCore {
   invoke: <T>(command: string, args?: Record<string, unknown>) => Promise<T>;
 }
 
+interface TauriEvent {
+  listen: <T>(
+    event: string,
+    handler: (event: { payload: T }) => void
+  ) => Promise<() => void>;
+}
+
 let tauriCorePromise: Promise<TauriCore> | null = null;
+let tauriEventPromise: Promise<TauriEvent> | null = null;
 
 const loadTauriCore = (): Promise<TauriCore> => {
   if (!tauriCorePromise) {
@@ -21,12 +34,26 @@ const loadTauriCore = (): Promise<TauriCore> => {
   return tauriCorePromise;
 };
 
+const loadTauriEvent = (): Promise<TauriEvent> => {
+  if (!tauriEventPromise) {
+    const moduleName = "@tauri-apps/api/event";
+    tauriEventPromise = import(moduleName).then((event) => ({
+      listen: event.listen,
+    }));
+  }
+  return tauriEventPromise;
+};
+
 const invokeGit = async <T>(
   command: string,
   args?: Record<string, unknown>
 ): Promise<T> => {
   const { invoke } = await loadTauriCore();
-  return invoke<T>(command, args);
+  try {
+    return await invoke<T>(command, args);
+  } catch (error) {
+    throw toGitError(error);
+  }
 };
 
 export const createTauriGitDriver = (): GitDriver => ({
@@ -45,8 +72,18 @@ export const createTauriGitDriver = (): GitDriver => ({
   createBranch: async (repoPath, branch, startPoint) =>
     invokeGit("git_create_branch", { repoPath, branch, startPoint }),
 
-  listWorktrees: async (repoPath) =>
-    invokeGit<WorktreeInfo[]>("git_list_worktrees", { repoPath }),
+  listBranches: async (repoPath, opts) =>
+    invokeGit<BranchInfo[]>("git_list_branches", {
+      repoPath,
+      kind: opts?.kind,
+      pattern: opts?.pattern,
+    }),
+
+  listWorktrees: async (repoPath, opts) =>
+    invokeGit<WorktreeInfo[]>("git_list_worktrees", {
+      repoPath,
+      includeStatus: opts?.includeStatus ?? false,
+    }),
 
   createWorktree: async (repoPath, opts: CreateWorktreeOpts) =>
     invokeGit("git_create_worktree", {
@@ -54,6 +91,14 @@ export const createTauriGitDriver = (): GitDriver => ({
       path: opts.path,
       branch: opts.branch,
       createBranch: opts.createBranch ?? false,
+      options: {
+        startPoint: opts.startPoint,
+        detached: opts.detached ?? false,
+        noCheckout: opts.noCheckout ?? false,
+        lock: opts.lock ?? false,
+        lockReason: opts.lockReason,
+        sparsePaths: opts.sparsePaths,
+      },
     }),
 
   removeWorktree: async (worktreePath, opts) =>
@@ -62,6 +107,30 @@ export const createTauriGitDriver = (): GitDriver => ({
       force: opts?.force ?? false,
     }),
 
+  cloneDeps: async (sourcePath, targetPath, paths, opts) => {
+    const onProgress = opts?.onProgress;
+    const unlisten = onProgress
+      ? await (await loadTauriEvent()).listen<CloneDepsProgress>(
+          "git/clone_deps_progress",
+          (event) => {
+            if (event.payload.targetPath === targetPath) {
+              onProgress(event.payload);
+            }
+          }
+        )
+      : null;
+    try {
+      return await invokeGit<CloneDepsResult>("git_clone_deps", {
+        sourcePath,
+        targetPath,
+        paths,
+        options: { allowHardlink: opts?.allowHardlink ?? false },
+      });
+    } finally {
+      unlisten?.();
+    }
+  },
+
   deleteBranch: async (repoPath, branch, force) =>
     invokeGit("git_delete_branch", {
       repoPath,
@@ -75,6 +144,15 @@ export const createTauriGitDriver = (): GitDriver => ({
   resetHard: async (repoPath, ref) =>
     invokeGit("git_reset_hard", { repoPath, gitRef: ref }),
 
+  merge: async (repoPath, branch, opts) =>
+    invokeGit<MergeResult>("git_merge", {
+      repoPath,
+      branch,
+      mode: opts.mode,
+      message: opts.message,
+      dryRun: opts.dryRun ?? false,
+    }),
+
   getStatus: async (repoPath) =>
     invokeGit<GitStatusEntry[]>("git_status", { repoPath }),
 
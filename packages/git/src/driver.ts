@@ -1,8 +1,14 @@
 import { createRpcGitDriver } from "./driver-rpc.js";
 import { isTauriRuntime } from "./runtime.js";
 import type {
+  BranchInfo,
+  CloneDepsOpts,
+  CloneDepsResult,
   CreateWorktreeOpts,
   GitStatusEntry,
+  ListBranchesOpts,
+  MergeOpts,
+  MergeResult,
   WorktreeInfo,
 } from "./types.js";
 
@@ -17,12 +23,25 @@ export interface GitDriver {
     branch: string,
     startPoint?: string
   ) => Promise<void>;
-  listWorktrees: (repoPath: string) => Promise<WorktreeInfo[]>;
+  listBranches: (
+    repoPath: string,
+    opts?: ListBranchesOpts
+  ) => Promise<BranchInfo[]>;
+  listWorktrees: (
+    repoPath: string,
+    opts?: { includeStatus?: boolean }
+  ) => Promise<WorktreeInfo[]>;
   createWorktree: (repoPath: string, opts: CreateWorktreeOpts) => Promise<void>;
   removeWorktree: (
     worktreePath: string,
     opts?: { force?: boolean }
   ) => Promise<void>;
+  cloneDeps: (
+    sourcePath: string,
+    targetPath: string,
+    paths: string[],
+    opts?: CloneDepsOpts
+  ) => Promise<CloneDepsResult>;
   deleteBranch: (
     repoPath: string,
     branch: string,
@@ -30,6 +49,11 @@ export interface GitDriver {
   ) => Promise<void>;
   getCurrentCommit: (repoPath: string) => Promise<string>;
   resetHard: (repoPath: string, ref: string) => Promise<void>;
+  merge: (
+    repoPath: string,
+    branch: string,
+    opts: MergeOpts
+  ) => Promise<MergeResult>;
   getStatus: (repoPath: string) => Promise<GitStatusEntry[]>;
   getChangedFiles: (repoPath: string) => Promise<string[]>;
 }
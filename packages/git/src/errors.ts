@@ -0,0 +1,49 @@
+export type GitErrorCode =
+  | "not_a_repo"
+  | "ref_invalid"
+  | "ref_not_found"
+  | "path_not_found"
+  | "exists"
+  | "branch_not_merged"
+  | "branch_checked_out"
+  | "dirty_worktree"
+  | "nothing_to_commit"
+  | "locked"
+  | "conflict"
+  | "not_fast_forward"
+  | "ref_changed"
+  | "invalid_argument"
+  | "invalid_params"
+  | "unknown_method"
+  | "other";
+
+export interface GitErrorPayload {
+  code: GitErrorCode;
+  message: string;
+  details?: unknown;
+}
+
+export class GitError extends Error {
+  readonly code: GitErrorCode;
+  readonly details?: unknown;
+
+  constructor(payload: GitErrorPayload) {
+    super(payload.message);
+    this.name = "GitError";
+    this.code = payload.code;
+    this.details = payload.details;
+  }
+}
+
+const isGitErrorPayload = (value: unknown): value is GitErrorPayload =>
+  typeof value === "object" &&
+  value !== null &&
+  typeof (value as GitErrorPayload).code === "string" &&
+  typeof (value as GitErrorPayload).message === "string";
+
+/**
+ * Converts an error returned by the native git backend into a GitError.
+ * Anything that is not a structured payload is passed through unchanged.
+ */
+export const toGitError = (error: unknown): unknown =>
+  isGitErrorPayload(error) ? new GitError(error) : error;
@@ -8,11 +8,24 @@ import {
   setGitDriver as setGitDriverImpl,
 } from "./driver.js";
 import type {
+  GitErrorCode as GitErrorCodeType,
+  GitErrorPayload as GitErrorPayloadType,
+} from "./errors.js";
+import type {
+  BranchInfo as BranchInfoType,
+  CloneDepsOpts as CloneDepsOptsType,
+  CloneDepsProgress as CloneDepsProgressType,
+  CloneDepsResult as CloneDepsResultType,
   CreateWorktreeOpts as CreateWorktreeOptsType,
   GitStatusEntry as GitStatusEntryType,
+  ListBranchesOpts as ListBranchesOptsType,
+  MergeOpts as MergeOptsType,
+  MergeResult as MergeResultType,
   WorktreeInfo as WorktreeInfoType,
 } from "./types.js";
 
+export { GitError } from "./errors.js";
+
 export const GIT_VERSION = "0.0.0";
 
 /**
@@ -67,6 +80,29 @@ export const isValidGitRef = (ref: string): boolean => {
   return true;
 };
 
+/**
+ * Revision expression validation, e.g. `HEAD~1`, `main^2` or `HEAD@{1}`.
+ * Only rejects what could be mistaken for an option or is never valid; git
+ * reports anything else when resolving it.
+ */
+export const isValidRevspec = (spec: string): boolean => {
+  if (!spec || spec.length === 0 || spec.length > 4096) {
+    return false;
+  }
+  // Cannot start with dash (prevents option injection)
+  if (spec.startsWith("-")) {
+    return false;
+  }
+  // Cannot contain control characters (0x00-0x1F and 0x7F)
+  for (const char of spec) {
+    const code = char.charCodeAt(0);
+    if (code <= 0x1f || code === 0x7f) {
+      return false;
+    }
+  }
+  return true;
+};
+
 /**
  * Validates a path is absolute and normalized.
  * Prevents path traversal attacks.
@@ -140,20 +176,40 @@ export const createBranch = async (
   await driver.createBranch(normalizePath(repoPath), branch, startPoint);
 };
 
+/**
+ * Lists branches with their tip, upstream tracking and the worktree they are
+ * checked out in, e.g. `{ pattern: "attempt/" }` for every attempt branch.
+ */
+export const listBranches = async (
+  repoPath: string,
+  opts?: ListBranchesOpts
+): Promise<BranchInfo[]> => {
+  const driver = await getGitDriver();
+  return driver.listBranches(normalizePath(repoPath), opts);
+};
+
+/**
+ * Lists worktrees. `includeStatus` also reports whether each one has
+ * uncommitted changes, at the cost of a status scan per worktree.
+ */
 export const listWorktrees = async (
-  repoPath: string
+  repoPath: string,
+  opts?: { includeStatus?: boolean }
 ): Promise<WorktreeInfo[]> => {
   const driver = await getGitDriver();
-  return driver.listWorktrees(normalizePath(repoPath));
+  return driver.listWorktrees(normalizePath(repoPath), opts);
 };
 
 export const createWorktree = async (
   repoPath: string,
   opts: CreateWorktreeOpts
 ): Promise<void> => {
-  if (!isValidGitRef(opts.branch)) {
+  if (opts.branch !== undefined && !isValidGitRef(opts.branch)) {
     throw new Error("Invalid branch name");
   }
+  if (opts.startPoint !== undefined && !isValidRevspec(opts.startPoint)) {
+    throw new Error("Invalid start point");
+  }
   // Normalize the worktree path to prevent traversal
   const normalizedPath = normalizePath(opts.path);
   const driver = await getGitDriver();
@@ -173,6 +229,27 @@ export const removeWorktree = async (
   await driver.removeWorktree(normalizedPath, opts);
 };
 
+/**
+ * Clones ignored directories such as `node_modules` from one worktree into
+ * another so a new worktree can skip a full install. Files are reflinked where
+ * the filesystem supports it, otherwise hardlinked or copied.
+ */
+export const cloneDeps = async (
+  sourcePath: string,
+  targetPath: string,
+  paths: string[],
+  opts?: CloneDepsOpts
+): Promise<CloneDepsResult> => {
+  // `paths` are worktree-relative; the backend rejects any that escape it.
+  const driver = await getGitDriver();
+  return driver.cloneDeps(
+    normalizePath(sourcePath),
+    normalizePath(targetPath),
+    paths,
+    opts
+  );
+};
+
 export const deleteBranch = async (
   repoPath: string,
   branch: string,
@@ -201,6 +278,22 @@ export const resetHard = async (
   await driver.resetHard(normalizePath(repoPath), ref);
 };
 
+/**
+ * Merges `branch` into the branch checked out at `repoPath`. Conflicts are
+ * reported in the result and leave the worktree untouched.
+ */
+export const mergeBranch = async (
+  repoPath: string,
+  branch: string,
+  opts: MergeOpts
+): Promise<MergeResult> => {
+  if (!isValidGitRef(branch)) {
+    throw new Error("Invalid branch name");
+  }
+  const driver = await getGitDriver();
+  return driver.merge(normalizePath(repoPath), branch, opts);
+};
+
 export const getStatus = async (
   repoPath: string
 ): Promise<GitStatusEntry[]> => {
@@ -228,6 +321,15 @@ export const setGitDriver = (
   setGitDriverImpl(driver);
 };
 
+export type BranchInfo = BranchInfoType;
+export type CloneDepsOpts = CloneDepsOptsType;
+export type CloneDepsProgress = CloneDepsProgressType;
+export type CloneDepsResult = CloneDepsResultType;
 export type CreateWorktreeOpts = CreateWorktreeOptsType;
 export type GitStatusEntry = GitStatusEntryType;
+export type ListBranchesOpts = ListBranchesOptsType;
+export type MergeOpts = MergeOptsType;
+export type MergeResult = MergeResultType;
 export type WorktreeInfo = WorktreeInfoType;
+export type GitErrorCode = GitErrorCodeType;
+export type GitErrorPayload = GitErrorPayloadType;
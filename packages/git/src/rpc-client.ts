@@ -1,5 +1,6 @@
 import { randomUUID } from "node:crypto";
 import { createConnection } from "node:net";
+import { GitError, type GitErrorPayload } from "./errors.js";
 
 interface RpcRequest {
   id: string;
@@ -9,14 +10,20 @@ interface RpcRequest {
 
 type RpcResponse<T> =
   | { id: string; ok: true; result: T }
-  | { id: string; ok: false; error: string };
+  | { id: string; ok: false; error: GitErrorPayload };
+
+interface RpcNotification {
+  method: string;
+  params: unknown;
+}
 
 const RPC_TIMEOUT_MS = 30_000;
 
 export const requestRpc = <T>(
   socketPath: string,
   method: string,
-  params: Record<string, unknown>
+  params: Record<string, unknown>,
+  onNotification?: (method: string, params: unknown) => void
 ): Promise<T> => {
   const id = randomUUID();
   const payload: RpcRequest = { id, method, params };
@@ -53,28 +60,36 @@ export const requestRpc = <T>(
 
     socket.on("data", (chunk) => {
       buffer += chunk;
-      const newlineIndex = buffer.indexOf("\n");
-      if (newlineIndex === -1) {
-        return;
-      }
-      const line = buffer.slice(0, newlineIndex).trim();
-      if (!line) {
-        settle(() => reject(new Error("Empty RPC response")));
-        return;
-      }
-      try {
-        const response = JSON.parse(line) as RpcResponse<T>;
-        if (response.id !== id) {
-          settle(() => reject(new Error("RPC response id mismatch")));
+      let newlineIndex = buffer.indexOf("\n");
+      while (newlineIndex !== -1 && !settled) {
+        const line = buffer.slice(0, newlineIndex).trim();
+        buffer = buffer.slice(newlineIndex + 1);
+        newlineIndex = buffer.indexOf("\n");
+        if (!line) {
+          settle(() => reject(new Error("Empty RPC response")));
           return;
         }
-        if (!response.ok) {
-          settle(() => reject(new Error(response.error)));
-          return;
+        try {
+          const message = JSON.parse(line) as
+            | RpcResponse<T>
+            | RpcNotification;
+          // Notifications (e.g. progress) carry no id and precede the response.
+          if (!("id" in message)) {
+            onNotification?.(message.method, message.params);
+            continue;
+          }
+          if (message.id !== id) {
+            settle(() => reject(new Error("RPC response id mismatch")));
+            return;
+          }
+          if (!message.ok) {
+            settle(() => reject(new GitError(message.error)));
+            return;
+          }
+          settle(() => resolve(message.result));
+        } catch (error) {
+          settle(() => reject(error));
         }
-        settle(() => resolve(response.result));
-      } catch (error) {
-        settle(() => reject(error));
       }
     });
 
@@ -1,7 +1,17 @@
 export interface CreateWorktreeOpts {
   path: string;
-  branch: string;
+  /** Required unless `detached` is set. */
+  branch?: string;
   createBranch?: boolean;
+  /** Any rev, e.g. a checkpoint ref; where a new branch or detached HEAD starts. */
+  startPoint?: string;
+  detached?: boolean;
+  /** Register the worktree without checking out any files. */
+  noCheckout?: boolean;
+  lock?: boolean;
+  lockReason?: string;
+  /** Check out only these directories (cone-mode sparse checkout). */
+  sparsePaths?: string[];
 }
 
 export type GitStatusKind =
@@ -11,10 +21,73 @@ export type GitStatusKind =
   | "renamed"
   | "typechange"
   | "untracked"
-  | "conflicted";
+  | "conflicted"
+  | "ignored";
+
+export interface BranchInfo {
+  name: string;
+  kind: "local" | "remote";
+  oid: string;
+  upstream: string | null;
+  ahead: number;
+  behind: number;
+  commitTime: number;
+  summary: string;
+  worktreePath: string | null;
+}
+
+export interface ListBranchesOpts {
+  kind?: "local" | "remote";
+  /** Short-name prefix or glob using `*` and `?`, e.g. `attempt/*`. */
+  pattern?: string;
+}
+
+export interface CloneDepsProgress {
+  targetPath: string;
+  /** Entry of `paths` currently being cloned. */
+  path: string;
+  filesDone: number;
+  filesTotal: number;
+  bytesDone: number;
+  bytesTotal: number;
+}
+
+export interface CloneDepsOpts {
+  /** Hardlink instead of copying when the filesystem can't reflink. */
+  allowHardlink?: boolean;
+  onProgress?: (progress: CloneDepsProgress) => void;
+}
+
+export interface CloneDepsResult {
+  cloned: string[];
+  /** Paths missing from the source or already present in the target. */
+  skipped: string[];
+  files: number;
+  bytes: number;
+  reflinked: number;
+  hardlinked: number;
+  copied: number;
+}
+
+export type MergeMode = "fastForwardOnly" | "merge" | "squash";
+
+export interface MergeOpts {
+  mode: MergeMode;
+  message?: string;
+  dryRun?: boolean;
+}
+
+export interface MergeResult {
+  status: "upToDate" | "fastForward" | "merged" | "squashed" | "conflicts";
+  oid: string | null;
+  conflicts: string[];
+}
 
 export interface GitStatusEntry {
   path: string;
   status: GitStatusKind;
+  indexStatus: GitStatusKind | null;
+  worktreeStatus: GitStatusKind | null;
+  origPath: string | null;
 }
 export type { WorktreeInfo } from "@forks-sh/protocol";
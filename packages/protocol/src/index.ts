@@ -221,7 +221,15 @@ export interface WorktreeInfo {
   bare: boolean;
   detached: boolean;
   locked: boolean;
+  lockReason: string | null;
   prunable: boolean;
+  /** Admin entry name under `.git/worktrees`; null for the main worktree. */
+  name: string | null;
+  gitdir: string;
+  pathExists: boolean;
+  /** Only set when listed with `includeStatus`. */
+  dirty: boolean | null;
+  changedFiles: number | null;
 }
 
 /** EnvProfile = reusable environment file configuration for workspaces */